@@ -0,0 +1,59 @@
+// Time to resolve the start position of a registration for growing file sizes.
+// the last lines are found from the end of the file, so the time stays the same.
+//
+// cargo bench --bench start_position
+
+use async_log_watch::{LogWatcher, RegisterOptions, StartPosition};
+
+use async_std::{
+    fs::{remove_file, File},
+    io::prelude::*,
+};
+
+use std::time::{Duration, Instant};
+
+const ITERATIONS: u32 = 100;
+
+async fn create_log_file(path: &str, size: usize) {
+    let line = format!("{}\n", "x".repeat(99));
+    let chunk = line.repeat(10_000);
+    let mut file = File::create(path).await.unwrap();
+    for _ in 0..size / chunk.len() {
+        file.write_all(chunk.as_bytes()).await.unwrap();
+    }
+    file.flush().await.unwrap();
+}
+
+async fn bench_register(path: &str, start_position: StartPosition) -> Duration {
+    let started = Instant::now();
+    for _ in 0..ITERATIONS {
+        let mut log_watcher = LogWatcher::new();
+        log_watcher
+            .register_with_options(
+                path,
+                |_| async {},
+                RegisterOptions::new().with_start_position(start_position),
+            )
+            .await
+            .unwrap();
+    }
+    started.elapsed() / ITERATIONS
+}
+
+#[async_std::main]
+async fn main() {
+    let path = "bench_start_position.log";
+
+    for size_mb in [1, 16, 256] {
+        create_log_file(path, size_mb * 1024 * 1024).await;
+
+        let end = bench_register(path, StartPosition::End).await;
+        let last_lines = bench_register(path, StartPosition::LastLines(100)).await;
+        println!(
+            "{:>4} MB : End {:>10.1?}  LastLines(100) {:>10.1?}",
+            size_mb, end, last_lines
+        );
+    }
+
+    remove_file(path).await.unwrap();
+}
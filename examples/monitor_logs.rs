@@ -17,7 +17,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             },
             None,
         )
-        .await;
+        .await?;
 
     log_watcher
         .monitoring(std::time::Duration::from_secs(1))
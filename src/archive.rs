@@ -0,0 +1,203 @@
+use crate::rt;
+
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// Rotated copies of a log file, e.g. `app.log.1`, `app.log.2.gz` or `app.log-20240101.zst`,
+// read to deliver the lines missed while the file was rotated.
+//
+// the copies compressed with gzip, zstd or bzip2 are read with the feature of the same name.
+// a copy is decompressed on a blocking thread, one chunk at a time.
+
+// extensions of the compressed copies.
+const COMPRESSED_EXTENSIONS: [&str; 3] = ["gz", "zst", "bz2"];
+
+// size of the chunks of a copy read at a time.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+// The content of a rotated copy, decompressed.
+pub(crate) struct Archive {
+    // `None` once the copy has been read to the end, or while a chunk is being read.
+    reader: Option<Box<dyn BufRead + Send>>,
+}
+
+// the rotated copies of the file, the newest first.
+pub(crate) async fn rotated_files(path: &Path) -> Vec<PathBuf> {
+    let (Some(dir), Some(file_name)) = (path.parent(), path.file_name()) else {
+        return Vec::new();
+    };
+    let Ok(files) = rt::read_dir_files(dir.to_path_buf()).await else {
+        return Vec::new();
+    };
+
+    let mut rotated: Vec<(SystemTime, PathBuf)> = Vec::new();
+    for file in files {
+        let is_rotated = (file.file_name())
+            .and_then(|name| name.to_str())
+            .zip(file_name.to_str())
+            .is_some_and(|(name, file_name)| is_rotated_name(name, file_name));
+        if !is_rotated {
+            continue;
+        }
+        if let Ok(modified) = rt::fs::metadata(&file).await.and_then(|m| m.modified()) {
+            rotated.push((modified, file));
+        }
+    }
+    rotated.sort_by(|a, b| b.cmp(a));
+    rotated.into_iter().map(|(_, file)| file).collect()
+}
+
+// read the first `len` bytes of the content of a rotated copy, decompressed.
+pub(crate) async fn read_head(path: &Path, len: u64) -> std::io::Result<Vec<u8>> {
+    let path = path.to_path_buf();
+    rt::spawn_blocking(move || {
+        let mut data = Vec::new();
+        decoder(&path)?.take(len).read_to_end(&mut data)?;
+        Ok(data)
+    })
+    .await
+}
+
+impl Archive {
+    // open a rotated copy to read its content from `offset`. the bytes before are decompressed
+    // and skipped.
+    pub(crate) async fn open(path: &Path, offset: u64) -> std::io::Result<Self> {
+        let path = path.to_path_buf();
+        rt::spawn_blocking(move || {
+            let mut reader = decoder(&path)?;
+            std::io::copy(&mut reader.by_ref().take(offset), &mut std::io::sink())?;
+            Ok(Self {
+                reader: Some(reader),
+            })
+        })
+        .await
+    }
+
+    // the next chunk of the content. returns an empty chunk at the end of the copy.
+    pub(crate) async fn read_chunk(&mut self) -> std::io::Result<Vec<u8>> {
+        let Some(mut reader) = self.reader.take() else {
+            return Ok(Vec::new());
+        };
+        let (reader, chunk) = rt::spawn_blocking(move || {
+            let chunk = reader.fill_buf().map(<[u8]>::to_vec);
+            if let Ok(chunk) = &chunk {
+                reader.consume(chunk.len());
+            }
+            (reader, chunk)
+        })
+        .await;
+        if matches!(&chunk, Ok(chunk) if !chunk.is_empty()) {
+            self.reader = Some(reader);
+        }
+        chunk
+    }
+}
+
+// "app.log.1", "app.log.1.gz" or "app.log-20240101" for "app.log".
+fn is_rotated_name(name: &str, file_name: &str) -> bool {
+    let Some(suffix) = name.strip_prefix(file_name) else {
+        return false;
+    };
+    let suffix = match suffix.rsplit_once('.') {
+        Some((suffix, extension)) if COMPRESSED_EXTENSIONS.contains(&extension) => suffix,
+        _ => suffix,
+    };
+    let mut chars = suffix.chars();
+    matches!(chars.next(), Some('.' | '-'))
+        && chars.as_str().starts_with(|c: char| c.is_ascii_digit())
+        && chars.all(|c| c.is_ascii_digit() || matches!(c, '.' | '-' | '_'))
+}
+
+// the decompressed content of a copy, buffered by chunks.
+fn decoder(path: &Path) -> std::io::Result<Box<dyn BufRead + Send>> {
+    let file = std::fs::File::open(path)?;
+    let extension = path.extension().and_then(|extension| extension.to_str());
+    Ok(match extension {
+        #[cfg(feature = "gzip")]
+        Some("gz") => Box::new(BufReader::with_capacity(
+            CHUNK_SIZE,
+            flate2::bufread::MultiGzDecoder::new(BufReader::new(file)),
+        )),
+        #[cfg(feature = "zstd")]
+        Some("zst") => Box::new(BufReader::with_capacity(
+            CHUNK_SIZE,
+            zstd::stream::read::Decoder::with_buffer(BufReader::new(file))?,
+        )),
+        #[cfg(feature = "bzip2")]
+        Some("bz2") => Box::new(BufReader::with_capacity(
+            CHUNK_SIZE,
+            bzip2::bufread::MultiBzDecoder::new(BufReader::new(file)),
+        )),
+        Some(extension) if COMPRESSED_EXTENSIONS.contains(&extension) => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                format!("the feature to decompress {:?} is not enabled", path),
+            ))
+        }
+        _ => Box::new(BufReader::with_capacity(CHUNK_SIZE, file)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_rotated_name;
+
+    #[test]
+    fn test_is_rotated_name() {
+        assert!(is_rotated_name("app.log.1", "app.log"));
+        assert!(is_rotated_name("app.log.2.gz", "app.log"));
+        assert!(is_rotated_name("app.log-20240101.zst", "app.log"));
+        assert!(is_rotated_name("app.log.2024-01-01.bz2", "app.log"));
+        assert!(!is_rotated_name("app.log", "app.log"));
+        assert!(!is_rotated_name("app.log.gz", "app.log"));
+        assert!(!is_rotated_name("app.log.tmp", "app.log"));
+        assert!(!is_rotated_name("app.log.1.tar", "app.log"));
+        assert!(!is_rotated_name("other.log.1", "app.log"));
+    }
+
+    #[cfg(feature = "async-std")]
+    #[async_std::test]
+    async fn test_read() {
+        use super::{read_head, Archive};
+
+        // more than a chunk
+        let data = b"line 1\nline 2\n".repeat(10_000);
+        let mut archives: Vec<(&str, Vec<u8>)> = Vec::new();
+        archives.push(("test-archive.log.1", data.clone()));
+        #[cfg(feature = "gzip")]
+        {
+            let mut encoder = flate2::write::GzEncoder::new(Vec::new(), Default::default());
+            std::io::Write::write_all(&mut encoder, &data).unwrap();
+            archives.push(("test-archive.log.1.gz", encoder.finish().unwrap()));
+        }
+        #[cfg(feature = "zstd")]
+        archives.push((
+            "test-archive.log.1.zst",
+            zstd::encode_all(&data[..], 0).unwrap(),
+        ));
+        #[cfg(feature = "bzip2")]
+        {
+            let mut encoder = bzip2::write::BzEncoder::new(Vec::new(), Default::default());
+            std::io::Write::write_all(&mut encoder, &data).unwrap();
+            archives.push(("test-archive.log.1.bz2", encoder.finish().unwrap()));
+        }
+
+        for (path, compressed) in archives {
+            std::fs::write(path, compressed).unwrap();
+            assert_eq!(read_head(path.as_ref(), 6).await.unwrap(), b"line 1");
+            // the content from an offset, in chunks
+            let mut archive = Archive::open(path.as_ref(), 7).await.unwrap();
+            let mut content = Vec::new();
+            loop {
+                let chunk = archive.read_chunk().await.unwrap();
+                if chunk.is_empty() {
+                    break;
+                }
+                content.extend_from_slice(&chunk);
+            }
+            assert_eq!(content, data[7..]);
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use crate::{rt, BatchCallback, LogEvent};
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// Events of a `register_batch` registration, delivered together once the batch has `max_lines`
+// events, `max_bytes` bytes of lines, or has waited for `max_wait`.
+//
+// the events are added to the batch by the callback of the registration, so that they are
+// filtered and parsed like the events of `register`. the offset of the file is only saved when
+// no event is waiting in the batch.
+//
+// a full batch is delivered by the task running the callbacks, and an expired one by the worker
+// of the file. the batches are delivered one at a time, in the order of their events.
+pub(crate) struct Batch {
+    max_lines: usize,
+    max_bytes: usize,
+    max_wait: Duration,
+    callback: BatchCallback,
+    pending: Mutex<Pending>,
+    // held while a batch is delivered.
+    delivering: rt::Mutex<()>,
+}
+
+#[derive(Default)]
+struct Pending {
+    events: Vec<LogEvent>,
+    bytes: usize,
+    // when the first event has been added.
+    since: Option<Instant>,
+    // when the pending flush timer fires.
+    timer: Option<Instant>,
+}
+
+impl Batch {
+    pub(crate) fn new(
+        max_lines: usize,
+        max_bytes: usize,
+        max_wait: Duration,
+        callback: BatchCallback,
+    ) -> Self {
+        Self {
+            max_lines,
+            max_bytes,
+            max_wait,
+            callback,
+            pending: Mutex::new(Pending::default()),
+            delivering: rt::Mutex::new(()),
+        }
+    }
+
+    // add an event, and deliver the batch if it is full.
+    pub(crate) async fn push(&self, log_event: LogEvent) {
+        let full = {
+            let mut pending = self.pending.lock().unwrap();
+            pending.bytes += log_event.bytes().map_or(0, <[u8]>::len);
+            pending.events.push(log_event);
+            pending.since.get_or_insert_with(Instant::now);
+            pending.events.len() >= self.max_lines || pending.bytes >= self.max_bytes
+        };
+        if full {
+            self.flush(Duration::ZERO).await;
+        }
+    }
+
+    // deliver the batch once its first event has waited for the timeout. returns whether the
+    // batch has been delivered.
+    pub(crate) async fn flush(&self, timeout: Duration) -> bool {
+        // the events are taken once the previous batch has been delivered
+        let _delivering = self.delivering.lock().await;
+        let events = {
+            let mut pending = self.pending.lock().unwrap();
+            match pending.since {
+                Some(since) if since.elapsed() >= timeout => {
+                    pending.bytes = 0;
+                    pending.since = None;
+                    std::mem::take(&mut pending.events)
+                }
+                _ => return false,
+            }
+        };
+        (self.callback)(events).await;
+        true
+    }
+
+    pub(crate) fn max_wait(&self) -> Duration {
+        self.max_wait
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.pending.lock().unwrap().since.is_none()
+    }
+
+    // the timeout of the flush timer to start for the waiting batch, if no timer is pending.
+    pub(crate) fn arm_timer(&self) -> Option<Duration> {
+        let mut pending = self.pending.lock().unwrap();
+        let now = Instant::now();
+        if pending.timer.is_some_and(|timer| timer > now) {
+            return None;
+        }
+        let timeout = self.max_wait.saturating_sub(pending.since?.elapsed());
+        pending.timer = Some(now + timeout);
+        Some(timeout)
+    }
+}
@@ -0,0 +1,127 @@
+use std::borrow::Cow;
+
+// How the bytes of the lines are decoded into `LogEvent::get_line`.
+//
+// The lines are split on the newline of the encoding, the offsets stay in bytes of the file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Encoding {
+    // UTF-8. a line which isn't valid is notified with its bytes and an `InvalidUtf8Error`.
+    #[default]
+    Utf8,
+    // UTF-8, the invalid sequences are replaced with U+FFFD.
+    Utf8Lossy,
+    // the lines are not decoded, available by `LogEvent::bytes`.
+    Raw,
+    // ISO-8859-1, transcoded into UTF-8.
+    #[cfg(feature = "encoding")]
+    Latin1,
+    // UTF-16 little endian, transcoded into UTF-8. the invalid sequences are replaced with
+    // U+FFFD and a byte order mark is removed.
+    #[cfg(feature = "encoding")]
+    Utf16Le,
+    // UTF-16 big endian, transcoded into UTF-8.
+    #[cfg(feature = "encoding")]
+    Utf16Be,
+}
+
+impl Encoding {
+    // the newline character in the encoding.
+    pub(crate) fn newline(&self) -> &'static [u8] {
+        match self {
+            #[cfg(feature = "encoding")]
+            Encoding::Utf16Le => &[b'\n', 0],
+            #[cfg(feature = "encoding")]
+            Encoding::Utf16Be => &[0, b'\n'],
+            _ => b"\n",
+        }
+    }
+
+    // the length of the bytes without an incomplete character at the end.
+    pub(crate) fn complete_len(&self, bytes: &[u8]) -> usize {
+        match self {
+            Encoding::Utf8 | Encoding::Utf8Lossy => match std::str::from_utf8(bytes) {
+                Err(e) if e.error_len().is_none() => e.valid_up_to(),
+                _ => bytes.len(),
+            },
+            #[cfg(feature = "encoding")]
+            Encoding::Utf16Le | Encoding::Utf16Be => {
+                let len = bytes.len() / 2 * 2;
+                let high = match self {
+                    Encoding::Utf16Le => len.checked_sub(1),
+                    _ => len.checked_sub(2),
+                };
+                // a high surrogate waits for the low one
+                match high.map(|i| bytes[i]) {
+                    Some(0xd8..=0xdb) => len - 2,
+                    _ => len,
+                }
+            }
+            _ => bytes.len(),
+        }
+    }
+
+    // the bytes of a line in UTF-8, except for `Raw`. the line is kept as is if it doesn't
+    // need to be transcoded.
+    pub(crate) fn transcode<'a>(&self, bytes: &'a [u8]) -> Cow<'a, [u8]> {
+        match self {
+            #[cfg(feature = "encoding")]
+            Encoding::Latin1 => {
+                let line: String = bytes.iter().map(|byte| *byte as char).collect();
+                Cow::Owned(line.into_bytes())
+            }
+            #[cfg(feature = "encoding")]
+            Encoding::Utf16Le => Cow::Owned(decode_utf16(bytes, u16::from_le_bytes)),
+            #[cfg(feature = "encoding")]
+            Encoding::Utf16Be => Cow::Owned(decode_utf16(bytes, u16::from_be_bytes)),
+            _ => Cow::Borrowed(bytes),
+        }
+    }
+}
+
+#[cfg(feature = "encoding")]
+fn decode_utf16(bytes: &[u8], from_bytes: fn([u8; 2]) -> u16) -> Vec<u8> {
+    let chunks = bytes.chunks_exact(2);
+    let odd = !chunks.remainder().is_empty();
+    let units = chunks.map(|chunk| from_bytes([chunk[0], chunk[1]]));
+    let mut line: String = char::decode_utf16(units)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect();
+    if odd {
+        line.push(char::REPLACEMENT_CHARACTER);
+    }
+    match line.strip_prefix('\u{feff}') {
+        Some(line) => line.as_bytes().to_vec(),
+        None => line.into_bytes(),
+    }
+}
+
+#[cfg(all(test, feature = "encoding"))]
+mod tests {
+    use super::Encoding;
+
+    #[test]
+    fn test_transcode() {
+        assert_eq!(
+            &*Encoding::Latin1.transcode(b"caf\xe9\n"),
+            "café\n".as_bytes()
+        );
+
+        let utf16le: Vec<u8> = "\u{feff}héllo\n"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        assert_eq!(
+            &*Encoding::Utf16Le.transcode(&utf16le),
+            "héllo\n".as_bytes()
+        );
+
+        let utf16be: Vec<u8> = "日本\n".encode_utf16().flat_map(u16::to_be_bytes).collect();
+        assert_eq!(&*Encoding::Utf16Be.transcode(&utf16be), "日本\n".as_bytes());
+
+        // an unpaired surrogate
+        assert_eq!(
+            &*Encoding::Utf16Le.transcode(&[0x00, 0xd8, b'a', 0]),
+            "\u{fffd}a".as_bytes()
+        );
+    }
+}
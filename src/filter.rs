@@ -0,0 +1,305 @@
+use crate::parser::{FieldValue, Fields};
+use crate::Error;
+
+use regex::{Regex, RegexBuilder, RegexSet};
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::ops::Not;
+use std::sync::Arc;
+
+// Filter of the lines notified to the callback, built from matchers and combinators.
+//
+// e.g. "ERROR but not HealthCheck":
+// `Filter::contains("ERROR").and(!Filter::contains_ignore_case("healthcheck"))`
+//
+// the regular expressions and substrings are the patterns of the filter, indexed in the order
+// they appear in the filter. the patterns which matched a notified line, the names of the
+// matched filters and the named capture groups are available from the `LogEvent`.
+#[derive(Debug, Clone)]
+pub struct Filter {
+    node: Node,
+    // number of patterns in the filter, to index the patterns of the right-hand side.
+    patterns: usize,
+}
+
+// The patterns, names and captures of the filter matching a line.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Matches {
+    pub(crate) patterns: Vec<usize>,
+    pub(crate) names: Vec<String>,
+    pub(crate) captures: BTreeMap<String, String>,
+}
+
+#[derive(Clone)]
+enum Node {
+    Regex(Regex),
+    // the regexes are kept to extract the captures of the matched patterns.
+    RegexSet(RegexSet, Vec<Regex>),
+    Contains(String),
+    // the substring is in lowercase
+    ContainsIgnoreCase(String),
+    Field(String, Arc<dyn Fn(&FieldValue) -> bool + Send + Sync>),
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+    Not(Box<Filter>),
+    Named(String, Box<Filter>),
+}
+
+impl Debug for Node {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Node::Regex(regex) => f.debug_tuple("Regex").field(regex).finish(),
+            Node::RegexSet(regex_set, _) => f.debug_tuple("RegexSet").field(regex_set).finish(),
+            Node::Contains(substring) => f.debug_tuple("Contains").field(substring).finish(),
+            Node::ContainsIgnoreCase(substring) => f
+                .debug_tuple("ContainsIgnoreCase")
+                .field(substring)
+                .finish(),
+            Node::Field(name, _) => f.debug_tuple("Field").field(name).finish(),
+            Node::And(lhs, rhs) => f.debug_tuple("And").field(lhs).field(rhs).finish(),
+            Node::Or(lhs, rhs) => f.debug_tuple("Or").field(lhs).field(rhs).finish(),
+            Node::Not(filter) => f.debug_tuple("Not").field(filter).finish(),
+            Node::Named(name, filter) => f.debug_tuple("Named").field(name).field(filter).finish(),
+        }
+    }
+}
+
+impl Filter {
+    // the line matches the regular expression.
+    pub fn regex(pattern: &str) -> Result<Self, Error> {
+        let regex = Regex::new(pattern).map_err(invalid_pattern)?;
+        Ok(Self::new(Node::Regex(regex)))
+    }
+
+    // the line matches the regular expression, ignoring the case.
+    pub fn regex_ignore_case(pattern: &str) -> Result<Self, Error> {
+        let regex = RegexBuilder::new(pattern)
+            .case_insensitive(true)
+            .build()
+            .map_err(invalid_pattern)?;
+        Ok(Self::new(Node::Regex(regex)))
+    }
+
+    // the line matches any of the regular expressions. (an include list)
+    pub fn any(patterns: Vec<&str>) -> Result<Self, Error> {
+        let regex_set = RegexSet::new(&patterns).map_err(invalid_pattern)?;
+        let regexes = patterns
+            .into_iter()
+            .map(|pattern| Regex::new(pattern).map_err(invalid_pattern))
+            .collect::<Result<_, _>>()?;
+        Ok(Self::new(Node::RegexSet(regex_set, regexes)))
+    }
+
+    // the line matches none of the regular expressions. (an exclude list)
+    pub fn none(patterns: Vec<&str>) -> Result<Self, Error> {
+        Ok(!Self::any(patterns)?)
+    }
+
+    // the line contains the substring.
+    pub fn contains(substring: &str) -> Self {
+        Self::new(Node::Contains(substring.to_owned()))
+    }
+
+    // the line contains the substring, ignoring the case.
+    pub fn contains_ignore_case(substring: &str) -> Self {
+        Self::new(Node::ContainsIgnoreCase(substring.to_lowercase()))
+    }
+
+    // the field parsed by the `LineParser` of the registration satisfies the predicate.
+    // a line without the field doesn't match.
+    pub fn field<F>(name: &str, predicate: F) -> Self
+    where
+        F: Fn(&FieldValue) -> bool + Send + Sync + 'static,
+    {
+        Self::new(Node::Field(name.to_owned(), Arc::new(predicate)))
+    }
+
+    // the field is equal to a string or an integer.
+    pub fn field_eq<V: Into<FieldValue>>(name: &str, value: V) -> Self {
+        let value = value.into();
+        Self::field(name, move |field| *field == value)
+    }
+
+    pub fn and(self, other: Filter) -> Self {
+        Self::new(Node::And(Box::new(self), Box::new(other)))
+    }
+
+    pub fn or(self, other: Filter) -> Self {
+        Self::new(Node::Or(Box::new(self), Box::new(other)))
+    }
+
+    // name the filter, reported by `LogEvent::matched_names` when it matches a line.
+    pub fn named(self, name: &str) -> Self {
+        Self::new(Node::Named(name.to_owned(), Box::new(self)))
+    }
+
+    // the matches of a line, or `None` if the filter doesn't match it.
+    pub(crate) fn find(&self, line: &str, fields: Option<&Fields>) -> Option<Matches> {
+        if !self.matches(line, fields) {
+            return None;
+        }
+        let mut matches = Matches::default();
+        self.collect(line, fields, 0, &mut matches);
+        Some(matches)
+    }
+
+    pub(crate) fn matches(&self, line: &str, fields: Option<&Fields>) -> bool {
+        match &self.node {
+            Node::Regex(regex) => regex.is_match(line),
+            Node::RegexSet(regex_set, _) => regex_set.is_match(line),
+            Node::Contains(substring) => line.contains(substring.as_str()),
+            Node::ContainsIgnoreCase(substring) => line.to_lowercase().contains(substring.as_str()),
+            Node::Field(name, predicate) => fields
+                .and_then(|fields| fields.get(name))
+                .is_some_and(|field| predicate(field)),
+            Node::And(lhs, rhs) => lhs.matches(line, fields) && rhs.matches(line, fields),
+            Node::Or(lhs, rhs) => lhs.matches(line, fields) || rhs.matches(line, fields),
+            Node::Not(filter) => !filter.matches(line, fields),
+            Node::Named(_, filter) => filter.matches(line, fields),
+        }
+    }
+
+    // collect the matches of the filter, whose first pattern has the index `first`. the
+    // filter matches the line, and the negated filters are skipped.
+    fn collect(&self, line: &str, fields: Option<&Fields>, first: usize, matches: &mut Matches) {
+        match &self.node {
+            Node::Regex(regex) => {
+                matches.patterns.push(first);
+                add_captures(regex, line, matches);
+            }
+            Node::RegexSet(regex_set, regexes) => {
+                for index in regex_set.matches(line).iter() {
+                    matches.patterns.push(first + index);
+                    add_captures(&regexes[index], line, matches);
+                }
+            }
+            Node::Contains(_) | Node::ContainsIgnoreCase(_) => matches.patterns.push(first),
+            Node::Field(..) | Node::Not(_) => {}
+            Node::And(lhs, rhs) => {
+                lhs.collect(line, fields, first, matches);
+                rhs.collect(line, fields, first + lhs.patterns, matches);
+            }
+            Node::Or(lhs, rhs) => {
+                // every matching side, to route a line matching several rules
+                if lhs.matches(line, fields) {
+                    lhs.collect(line, fields, first, matches);
+                }
+                if rhs.matches(line, fields) {
+                    rhs.collect(line, fields, first + lhs.patterns, matches);
+                }
+            }
+            Node::Named(name, filter) => {
+                matches.names.push(name.clone());
+                filter.collect(line, fields, first, matches);
+            }
+        }
+    }
+
+    fn new(node: Node) -> Self {
+        let patterns = match &node {
+            Node::Regex(_) | Node::Contains(_) | Node::ContainsIgnoreCase(_) => 1,
+            Node::RegexSet(regex_set, _) => regex_set.len(),
+            Node::Field(..) => 0,
+            Node::And(lhs, rhs) | Node::Or(lhs, rhs) => lhs.patterns + rhs.patterns,
+            Node::Not(filter) | Node::Named(_, filter) => filter.patterns,
+        };
+        Self { node, patterns }
+    }
+}
+
+fn invalid_pattern(e: regex::Error) -> Error {
+    Error::InvalidPattern(e.to_string())
+}
+
+// the named capture groups of the first match. a group already captured by another pattern is
+// kept.
+fn add_captures(regex: &Regex, line: &str, matches: &mut Matches) {
+    if regex.capture_names().flatten().next().is_none() {
+        return;
+    }
+    let Some(captures) = regex.captures(line) else {
+        return;
+    };
+    for name in regex.capture_names().flatten() {
+        if let Some(capture) = captures.name(name) {
+            (matches.captures)
+                .entry(name.to_owned())
+                .or_insert_with(|| capture.as_str().to_owned());
+        }
+    }
+}
+
+impl Not for Filter {
+    type Output = Filter;
+
+    fn not(self) -> Self::Output {
+        Self::new(Node::Not(Box::new(self)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Filter;
+    use crate::parser::{FieldValue, Fields};
+    use crate::Error;
+
+    #[test]
+    fn test_filter() {
+        let filter = Filter::contains("ERROR").and(!Filter::contains_ignore_case("healthcheck"));
+        assert!(filter.matches("ERROR db timeout", None));
+        assert!(!filter.matches("ERROR HealthCheck failed", None));
+        assert!(!filter.matches("INFO started", None));
+
+        let filter = Filter::any(vec!["^WARN", "^ERROR"])
+            .unwrap()
+            .and(Filter::none(vec!["retry"]).unwrap());
+        assert!(filter.matches("WARN disk", None));
+        assert!(!filter.matches("WARN retry 1", None));
+
+        let filter = Filter::regex_ignore_case("^error")
+            .unwrap()
+            .or(Filter::contains("panic"));
+        assert!(filter.matches("Error: x", None));
+        assert!(filter.matches("thread panicked", None));
+        assert!(!filter.matches("INFO", None));
+
+        let mut fields = Fields::new();
+        fields.insert("status".to_owned(), FieldValue::Integer(503));
+        fields.insert("level".to_owned(), FieldValue::from("error"));
+        let filter = Filter::field("status", |status| status.as_i64() >= Some(500))
+            .and(Filter::field_eq("level", "error"));
+        assert!(filter.matches("", Some(&fields)));
+        assert!(!filter.matches("", None));
+        assert!(!Filter::field_eq("status", 200).matches("", Some(&fields)));
+
+        assert!(matches!(
+            Filter::regex("(unclosed"),
+            Err(Error::InvalidPattern(_))
+        ));
+    }
+
+    #[test]
+    fn test_filter_matches() {
+        let filter = Filter::any(vec!["^ERROR", r"status=(?P<status>\d+)", "timeout"])
+            .unwrap()
+            .and(!Filter::contains("HealthCheck"));
+        let matches = filter.find("ERROR status=504 id=7", None).unwrap();
+        assert_eq!(matches.patterns, vec![0, 1]);
+        assert_eq!(matches.captures["status"], "504");
+        assert!(filter.find("ERROR HealthCheck", None).is_none());
+
+        let filter = Filter::regex(r"id=(?P<request_id>\w+)")
+            .unwrap()
+            .named("request")
+            .or(Filter::contains("panic").named("panic"))
+            .or(Filter::contains("ERROR").named("error"));
+        let matches = filter.find("ERROR panic id=a1", None).unwrap();
+        assert_eq!(matches.patterns, vec![0, 1, 2]);
+        assert_eq!(matches.names, vec!["request", "panic", "error"]);
+        assert_eq!(matches.captures["request_id"], "a1");
+        let matches = filter.find("ERROR", None).unwrap();
+        assert_eq!(matches.patterns, vec![2]);
+        assert_eq!(matches.names, vec!["error"]);
+        assert!(matches.captures.is_empty());
+    }
+}
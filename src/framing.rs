@@ -0,0 +1,279 @@
+use crate::rt::io::*;
+use crate::{Encoding, Error};
+
+use regex::bytes::Regex;
+use std::io::SeekFrom;
+
+// number of bytes read at once when looking for a regex delimiter.
+const REGEX_CHUNK_SIZE: usize = 4 * 1024;
+
+// number of bytes of a record of a size or a length read at once, so that the memory follows
+// the bytes actually in the file.
+const READ_CHUNK_SIZE: usize = 64 * 1024;
+
+// default maximum length of a length-prefixed record.
+const DEFAULT_MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+
+// How the content of a file is split into records, each notified as a line.
+//
+// A record is delivered once it is complete, a partial record at the end of the file is read
+// again on the next event. the offsets are tracked in bytes of the file as for the lines.
+#[derive(Debug, Clone)]
+pub struct Framing {
+    kind: Kind,
+    max_frame_size: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+enum Kind {
+    #[default]
+    Newline,
+    Delimiter(Vec<u8>),
+    Regex(Regex),
+    FixedSize(usize),
+    LengthPrefixed {
+        big_endian: bool,
+    },
+}
+
+impl Framing {
+    // lines ending with the newline of the encoding, a trailing '\r' is removed. (default)
+    pub fn newline() -> Self {
+        Self::new(Kind::Newline)
+    }
+
+    // records ending with a delimiter, e.g. b"\0" or b"\r\n". the delimiter must not be empty.
+    pub fn delimiter(delimiter: &[u8]) -> Result<Self, Error> {
+        if delimiter.is_empty() {
+            return Err(Error::InvalidFraming("empty delimiter".to_owned()));
+        }
+        Ok(Self::new(Kind::Delimiter(delimiter.to_vec())))
+    }
+
+    // records ending with a match of the regular expression. a match at the end of the bytes
+    // written so far is taken as the delimiter.
+    pub fn regex(pattern: &str) -> Result<Self, Error> {
+        let regex = Regex::new(pattern).map_err(|e| Error::InvalidPattern(e.to_string()))?;
+        Ok(Self::new(Kind::Regex(regex)))
+    }
+
+    // records of a fixed number of bytes. the size must not be 0.
+    pub fn fixed_size(size: usize) -> Result<Self, Error> {
+        if size == 0 {
+            return Err(Error::InvalidFraming("record size of 0".to_owned()));
+        }
+        Ok(Self::new(Kind::FixedSize(size)))
+    }
+
+    // binary records preceded by their length as a big endian u32.
+    pub fn length_prefixed() -> Self {
+        Self::new(Kind::LengthPrefixed { big_endian: true })
+    }
+
+    // binary records preceded by their length as a little endian u32.
+    pub fn length_prefixed_le() -> Self {
+        Self::new(Kind::LengthPrefixed { big_endian: false })
+    }
+
+    // maximum length of a length-prefixed record. a larger length is notified as a
+    // `FrameError`, the file isn't read further. (default: 16 MiB)
+    pub fn with_max_frame_size(mut self, max_frame_size: usize) -> Self {
+        self.max_frame_size = max_frame_size;
+        self
+    }
+
+    pub(crate) fn is_newline(&self) -> bool {
+        matches!(self.kind, Kind::Newline)
+    }
+
+    pub(crate) fn fixed_size_of(&self) -> Option<usize> {
+        match self.kind {
+            Kind::FixedSize(size) => Some(size),
+            _ => None,
+        }
+    }
+
+    // read the next complete record into `frame`, without its delimiter or length. returns the
+    // number of bytes of the record in the file, or `None` at EOF or for a partial record.
+    pub(crate) async fn read<R: Reader>(
+        &self,
+        reader: &mut R,
+        frame: &mut Vec<u8>,
+        encoding: Encoding,
+    ) -> std::io::Result<Option<u64>> {
+        match &self.kind {
+            Kind::Newline => {
+                let newline = encoding.newline();
+                if !read_line(reader, frame, newline).await? {
+                    return Ok(None);
+                }
+                let len = frame.len() as u64;
+                frame.truncate(frame.len() - newline.len());
+                let cr = carriage_return(newline);
+                while frame.ends_with(&cr) {
+                    frame.truncate(frame.len() - cr.len());
+                }
+                Ok(Some(len))
+            }
+            Kind::Delimiter(delimiter) => {
+                let last = delimiter[delimiter.len() - 1];
+                loop {
+                    let len = reader.read_until(last, frame).await?;
+                    if len == 0 || frame.last() != Some(&last) {
+                        return Ok(None);
+                    }
+                    if frame.ends_with(delimiter) {
+                        let len = frame.len() as u64;
+                        frame.truncate(frame.len() - delimiter.len());
+                        return Ok(Some(len));
+                    }
+                }
+            }
+            Kind::Regex(regex) => {
+                let mut chunk = vec![0; REGEX_CHUNK_SIZE];
+                let mut eof = false;
+                loop {
+                    // a match at the end of the bytes read so far may go on with the next ones
+                    let found = (regex.find_iter(frame))
+                        .find(|m| !m.is_empty())
+                        .filter(|m| m.end() < frame.len() || eof)
+                        .map(|m| (m.start(), m.end()));
+                    if let Some((start, end)) = found {
+                        // the bytes after the delimiter belong to the next record
+                        let over_read = (frame.len() - end) as i64;
+                        reader.seek(SeekFrom::Current(-over_read)).await?;
+                        frame.truncate(start);
+                        return Ok(Some(end as u64));
+                    }
+                    if eof {
+                        return Ok(None);
+                    }
+                    let len = reader.read(&mut chunk).await?;
+                    eof = len == 0;
+                    frame.extend_from_slice(&chunk[..len]);
+                }
+            }
+            Kind::FixedSize(size) => {
+                let complete = read_exact_len(reader, frame, *size).await?;
+                Ok(complete.then_some(*size as u64))
+            }
+            Kind::LengthPrefixed { big_endian } => {
+                let mut prefix = Vec::with_capacity(4);
+                if !read_exact_len(reader, &mut prefix, 4).await? {
+                    return Ok(None);
+                }
+                let prefix = [prefix[0], prefix[1], prefix[2], prefix[3]];
+                let len = match big_endian {
+                    true => u32::from_be_bytes(prefix),
+                    false => u32::from_le_bytes(prefix),
+                } as usize;
+                if len > self.max_frame_size {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!(
+                            "record of {} bytes, more than the maximum of {} bytes",
+                            len, self.max_frame_size
+                        ),
+                    ));
+                }
+                let complete = read_exact_len(reader, frame, len).await?;
+                Ok(complete.then_some(4 + len as u64))
+            }
+        }
+    }
+
+    // the length of a partial record which can be delivered on its own, keeping back what may
+    // be the start of the delimiter. the records of a size or a length are never partial.
+    pub(crate) fn partial_len(&self, bytes: &[u8], encoding: Encoding) -> usize {
+        match &self.kind {
+            Kind::Newline => {
+                let newline = encoding.newline();
+                let cr = carriage_return(newline);
+                let mut len = bytes.len() / newline.len() * newline.len();
+                while bytes[..len].ends_with(&cr) {
+                    len -= cr.len();
+                }
+                len
+            }
+            Kind::Delimiter(delimiter) => {
+                let kept = (1..delimiter.len())
+                    .rev()
+                    .find(|n| bytes.ends_with(&delimiter[..*n]))
+                    .unwrap_or(0);
+                bytes.len() - kept
+            }
+            Kind::Regex(_) => bytes.len(),
+            Kind::FixedSize(_) | Kind::LengthPrefixed { .. } => 0,
+        }
+    }
+
+    fn new(kind: Kind) -> Self {
+        Self {
+            kind,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+        }
+    }
+}
+
+impl Default for Framing {
+    fn default() -> Self {
+        Self::newline()
+    }
+}
+
+// '\r' in the encoding of the newline.
+fn carriage_return(newline: &[u8]) -> Vec<u8> {
+    (newline.iter())
+        .map(|byte| if *byte == b'\n' { b'\r' } else { *byte })
+        .collect()
+}
+
+// read a line ending with the newline of the encoding into `line`. returns false at EOF or
+// when only a partial line has been written so far.
+async fn read_line<R: Reader>(
+    reader: &mut R,
+    line: &mut Vec<u8>,
+    newline: &[u8],
+) -> std::io::Result<bool> {
+    // the newline is aligned on its length, e.g. "\n\0" of UTF-16LE at an even offset
+    let newline_pos = newline.iter().position(|byte| *byte == b'\n').unwrap_or(0);
+    loop {
+        let len = reader.read_until(b'\n', line).await?;
+        if len == 0 || !line.ends_with(b"\n") {
+            return Ok(false);
+        }
+        if (line.len() - 1) % newline.len() != newline_pos {
+            continue;
+        }
+        for _ in newline_pos + 1..newline.len() {
+            let mut byte = [0];
+            if reader.read_exact(&mut byte).await.is_err() {
+                return Ok(false);
+            }
+            line.push(byte[0]);
+        }
+        if line.ends_with(newline) {
+            return Ok(true);
+        }
+    }
+}
+
+// append `len` bytes to `frame`. returns false if the file ends before. the frame only grows
+// by the bytes read.
+async fn read_exact_len<R: Reader>(
+    reader: &mut R,
+    frame: &mut Vec<u8>,
+    len: usize,
+) -> std::io::Result<bool> {
+    let end = frame.len() + len;
+    while frame.len() < end {
+        let filled = frame.len();
+        frame.resize(filled + (end - filled).min(READ_CHUNK_SIZE), 0);
+        let n = reader.read(&mut frame[filled..]).await?;
+        frame.truncate(filled + n);
+        if n == 0 {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
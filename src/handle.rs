@@ -0,0 +1,541 @@
+use crate::batch::Batch;
+use crate::monitor::{EventLoop, Message, Shared};
+use crate::rt::{self, Mutex};
+use crate::tail::TailState;
+use crate::{
+    unwatch_unused_dir, watch_dir, BatchCallback, Error, FilePattern, FilePatterns, Filter,
+    LogCallbacks, LogEvent, LogStream, RegisterOptions, Registration, SubscriptionId,
+    WatcherConfig,
+};
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use shellexpand::tilde;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+// reading state of every registration of a file. a state is locked on its own while the file is
+// read, so that the other files aren't blocked by its callbacks.
+pub(crate) type TailStates = HashMap<(PathBuf, SubscriptionId), Arc<Mutex<TailState>>>;
+
+// Cloneable handle of a `LogWatcher`, obtained by `LogWatcher::handle`.
+//
+// The files can be registered, stopped and changed through the handle while the monitoring is
+// running, the watched directories are updated right away.
+#[derive(Clone)]
+pub struct LogWatcherHandle {
+    pub(crate) log_callbacks: Arc<Mutex<LogCallbacks>>,
+    pub(crate) file_patterns: Arc<Mutex<FilePatterns>>,
+    pub(crate) tail_states: Arc<Mutex<TailStates>>,
+    pub(crate) watcher: Arc<Mutex<Option<RecommendedWatcher>>>,
+    // the messages of the running monitoring.
+    sender: Arc<Mutex<Option<rt::UnboundedSender<Message>>>>,
+    config: WatcherConfig,
+    next_id: Arc<AtomicU64>,
+}
+
+impl LogWatcherHandle {
+    pub(crate) fn new(config: WatcherConfig) -> Self {
+        Self {
+            log_callbacks: Arc::new(Mutex::new(HashMap::new())),
+            file_patterns: Arc::new(Mutex::new(Vec::new())),
+            tail_states: Arc::new(Mutex::new(HashMap::new())),
+            watcher: Arc::new(Mutex::new(None)),
+            sender: Arc::new(Mutex::new(None)),
+            config,
+            next_id: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    pub async fn change_file_path<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        old_path: P,
+        new_path: Q,
+    ) -> Result<(), Error> {
+        // change into absolute path
+        let old_path = self.make_absolute_path(old_path.as_ref())?;
+        let new_path = self.make_file_path(new_path.as_ref())?;
+
+        let mut log_callbacks = self.log_callbacks.lock().await;
+        let Some(registrations) = log_callbacks.remove(&old_path) else {
+            return Ok(());
+        };
+        self.tail_states
+            .lock()
+            .await
+            .retain(|(path, _), _| *path != old_path);
+        drop(log_callbacks);
+
+        // the new file is opened without the locks, the callbacks may be notified
+        let mut tail_states = Vec::new();
+        for registration in registrations.iter() {
+            let tail_state = TailState::open(&new_path, registration).await;
+            if tail_state.has_catch_up() {
+                self.catch_up_if_monitoring(&new_path).await;
+            }
+            tail_states.push((
+                (new_path.clone(), registration.id),
+                Arc::new(Mutex::new(tail_state)),
+            ));
+        }
+
+        let mut log_callbacks = self.log_callbacks.lock().await;
+        self.tail_states.lock().await.extend(tail_states);
+        log_callbacks
+            .entry(new_path.clone())
+            .or_default()
+            .extend(registrations);
+        let mut watcher = self.watcher.lock().await;
+        if let Some(watcher) = &mut *watcher {
+            watcher
+                .watch(watch_dir(&new_path), RecursiveMode::NonRecursive)
+                .map_err(Error::EventError)?;
+            let file_patterns = self.file_patterns.lock().await;
+            unwatch_unused_dir(
+                watcher,
+                &log_callbacks,
+                &file_patterns,
+                watch_dir(&old_path),
+            )?;
+        }
+        Ok(())
+    }
+
+    pub async fn stop_monitoring_file<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        // change into absolute path
+        let path = self.make_absolute_path(path.as_ref())?;
+
+        let mut log_callbacks = self.log_callbacks.lock().await;
+        if log_callbacks.remove(&path).is_some() {
+            self.tail_states
+                .lock()
+                .await
+                .retain(|(tail_path, _), _| *tail_path != path);
+            let mut watcher = self.watcher.lock().await;
+            if let Some(watcher) = &mut *watcher {
+                let file_patterns = self.file_patterns.lock().await;
+                unwatch_unused_dir(watcher, &log_callbacks, &file_patterns, watch_dir(&path))?;
+            }
+        }
+        Ok(())
+    }
+
+    // remove a registration. the files registered by a file pattern are removed with it.
+    pub async fn unsubscribe(&self, id: SubscriptionId) -> Result<(), Error> {
+        let mut log_callbacks = self.log_callbacks.lock().await;
+        let mut file_patterns = self.file_patterns.lock().await;
+
+        // the directories which may not be used anymore
+        let mut removed_dirs = Vec::new();
+        log_callbacks.retain(|path, registrations| {
+            let len = registrations.len();
+            registrations.retain(|registration| registration.id != id);
+            if registrations.len() != len {
+                removed_dirs.push(watch_dir(path).to_path_buf());
+            }
+            !registrations.is_empty()
+        });
+        file_patterns.retain(|(file_pattern, registration)| {
+            if registration.id == id {
+                removed_dirs.push(file_pattern.dir().to_path_buf());
+            }
+            registration.id != id
+        });
+        self.tail_states
+            .lock()
+            .await
+            .retain(|(_, tail_id), _| *tail_id != id);
+
+        if let Some(watcher) = &mut *self.watcher.lock().await {
+            for dir in removed_dirs {
+                unwatch_unused_dir(watcher, &log_callbacks, &file_patterns, &dir)?;
+            }
+        }
+        Ok(())
+    }
+
+    // helper function to convert a relative path into an absolute path
+    pub(crate) fn make_absolute_path(&self, path: &Path) -> Result<PathBuf, Error> {
+        // a path which isn't valid UTF-8 can't start with a tilde to expand
+        let expanded_path = match path.to_str() {
+            Some(path) => PathBuf::from(tilde(path).into_owned()),
+            None => path.to_path_buf(),
+        };
+
+        if expanded_path.is_absolute() {
+            Ok(expanded_path)
+        } else {
+            let current_dir = std::env::current_dir().map_err(Error::CurrentDirUnavailable)?;
+            Ok(current_dir.join(expanded_path))
+        }
+    }
+
+    // the absolute path of a file to register, which must have a file name.
+    fn make_file_path(&self, path: &Path) -> Result<PathBuf, Error> {
+        let absolute_path = self.make_absolute_path(path)?;
+        if absolute_path.file_name().is_none() {
+            return Err(Error::InvalidPath(path.to_path_buf()));
+        }
+        Ok(absolute_path)
+    }
+
+    // register a file path and its associated callback function.
+    pub async fn register<P: AsRef<Path>, F, Fut>(
+        &self,
+        path: P,
+        callback: F,
+        patterns: Option<Vec<&str>>,
+    ) -> Result<SubscriptionId, Error>
+    where
+        F: Fn(LogEvent) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + Sync + 'static,
+    {
+        let mut options = RegisterOptions::new();
+        if let Some(patterns) = patterns {
+            options = options.with_patterns(patterns);
+        }
+        self.register_with_options(path, callback, options).await
+    }
+
+    // register a file path and its associated callback function with the options.
+    pub async fn register_with_options<P: AsRef<Path>, F, Fut>(
+        &self,
+        path: P,
+        callback: F,
+        options: RegisterOptions,
+    ) -> Result<SubscriptionId, Error>
+    where
+        F: Fn(LogEvent) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + Sync + 'static,
+    {
+        let path = self.make_file_path(path.as_ref())?;
+        let registration = self.make_registration(callback, options)?;
+        self.add_registration(path, registration).await
+    }
+
+    // register a file path and a callback receiving the events in batches. a batch is delivered
+    // once it has `max_lines` events, `max_bytes` bytes of lines, or has waited for `max_wait`.
+    pub async fn register_batch<P: AsRef<Path>, F, Fut>(
+        &self,
+        path: P,
+        max_lines: usize,
+        max_bytes: usize,
+        max_wait: Duration,
+        callback: F,
+    ) -> Result<SubscriptionId, Error>
+    where
+        F: Fn(Vec<LogEvent>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + Sync + 'static,
+    {
+        let options = RegisterOptions::new();
+        self.register_batch_with_options(path, max_lines, max_bytes, max_wait, callback, options)
+            .await
+    }
+
+    // register a file path and a callback receiving the events in batches, with the options.
+    // the offset is saved once the callback of the batch has returned.
+    pub async fn register_batch_with_options<P: AsRef<Path>, F, Fut>(
+        &self,
+        path: P,
+        max_lines: usize,
+        max_bytes: usize,
+        max_wait: Duration,
+        callback: F,
+        options: RegisterOptions,
+    ) -> Result<SubscriptionId, Error>
+    where
+        F: Fn(Vec<LogEvent>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + Sync + 'static,
+    {
+        let path = self.make_file_path(path.as_ref())?;
+
+        let callback: BatchCallback = Arc::new(
+            move |log_events: Vec<LogEvent>| -> Pin<Box<dyn Future<Output = ()> + Send + Sync>> {
+                Box::pin(callback(log_events))
+            },
+        );
+        let batch = Arc::new(Batch::new(max_lines, max_bytes, max_wait, callback));
+
+        // the events of the registration are added to the batch
+        let batch_clone = Arc::clone(&batch);
+        let mut registration = self.make_registration(
+            move |log_event: LogEvent| {
+                let batch = Arc::clone(&batch_clone);
+                async move { batch.push(log_event).await }
+            },
+            options,
+        )?;
+        registration.batch = Some(batch);
+        self.add_registration(path, registration).await
+    }
+
+    async fn add_registration(
+        &self,
+        path: PathBuf,
+        registration: Registration,
+    ) -> Result<SubscriptionId, Error> {
+        let id = registration.id;
+        self.watch_if_monitoring(watch_dir(&path)).await?;
+        let tail_state = TailState::open(&path, &registration).await;
+        if tail_state.has_catch_up() {
+            self.catch_up_if_monitoring(&path).await;
+        }
+
+        let mut log_callbacks = self.log_callbacks.lock().await;
+        self.tail_states
+            .lock()
+            .await
+            .insert((path.clone(), id), Arc::new(Mutex::new(tail_state)));
+        log_callbacks.entry(path).or_default().push(registration);
+        Ok(id)
+    }
+
+    // register a file path and receive its events as a stream.
+    pub async fn register_stream<P: AsRef<Path>>(
+        &self,
+        path: P,
+        patterns: Option<Vec<&str>>,
+    ) -> Result<LogStream, Error> {
+        let mut options = RegisterOptions::new();
+        if let Some(patterns) = patterns {
+            options = options.with_patterns(patterns);
+        }
+        self.register_stream_with_options(path, options).await
+    }
+
+    // register a file path with the options and receive its events as a stream.
+    pub async fn register_stream_with_options<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: RegisterOptions,
+    ) -> Result<LogStream, Error> {
+        let (sender, receiver) = rt::bounded(options.stream_capacity);
+        let id = self
+            .register_with_options(
+                path,
+                move |log_event: LogEvent| {
+                    let sender = sender.clone();
+                    async move {
+                        // the stream may have been dropped
+                        let _ = sender.send(log_event).await;
+                    }
+                },
+                options,
+            )
+            .await?;
+        Ok(LogStream::new(id, receiver, self.clone()))
+    }
+
+    // register the files matching a glob pattern (e.g. "/var/log/app/*.log"), including the
+    // files created after the registration. a removed file stops being monitored.
+    pub async fn register_glob<F, Fut>(
+        &self,
+        pattern: &str,
+        callback: F,
+        patterns: Option<Vec<&str>>,
+    ) -> Result<SubscriptionId, Error>
+    where
+        F: Fn(LogEvent) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + Sync + 'static,
+    {
+        let mut options = RegisterOptions::new();
+        if let Some(patterns) = patterns {
+            options = options.with_patterns(patterns);
+        }
+        self.register_pattern_with_options(FilePattern::glob(pattern)?, callback, options)
+            .await
+    }
+
+    // register the files in a directory whose name matches a regular expression.
+    pub async fn register_dir<P: AsRef<Path>, F, Fut>(
+        &self,
+        dir: P,
+        file_name: &str,
+        callback: F,
+        patterns: Option<Vec<&str>>,
+    ) -> Result<SubscriptionId, Error>
+    where
+        F: Fn(LogEvent) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + Sync + 'static,
+    {
+        let mut options = RegisterOptions::new();
+        if let Some(patterns) = patterns {
+            options = options.with_patterns(patterns);
+        }
+        self.register_pattern_with_options(FilePattern::regex(dir, file_name)?, callback, options)
+            .await
+    }
+
+    // register the files matching a file pattern with the options.
+    pub async fn register_pattern_with_options<F, Fut>(
+        &self,
+        file_pattern: FilePattern,
+        callback: F,
+        options: RegisterOptions,
+    ) -> Result<SubscriptionId, Error>
+    where
+        F: Fn(LogEvent) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + Sync + 'static,
+    {
+        let dir = self.make_absolute_path(file_pattern.dir())?;
+        let file_pattern = file_pattern.with_dir(dir);
+        let registration = self.make_registration(callback, options)?;
+        let id = registration.id;
+        self.watch_if_monitoring(file_pattern.dir()).await?;
+
+        // the files already in the directory, opened without the locks
+        let mut tail_states = Vec::new();
+        if let Ok(files) = rt::read_dir_files(file_pattern.dir().to_path_buf()).await {
+            for path in files {
+                if file_pattern.matches(&path) {
+                    let tail_state = TailState::open(&path, &registration).await;
+                    if tail_state.has_catch_up() {
+                        self.catch_up_if_monitoring(&path).await;
+                    }
+                    tail_states.push((path, tail_state));
+                }
+            }
+        }
+
+        let mut log_callbacks = self.log_callbacks.lock().await;
+        for (path, tail_state) in tail_states {
+            self.tail_states
+                .lock()
+                .await
+                .insert((path.clone(), id), Arc::new(Mutex::new(tail_state)));
+            log_callbacks.entry(path).or_default().push(Registration {
+                from_pattern: true,
+                ..registration.clone()
+            });
+        }
+
+        self.file_patterns
+            .lock()
+            .await
+            .push((file_pattern, registration));
+        Ok(id)
+    }
+
+    // watch the directory right away when the monitoring is running. the registration isn't
+    // added if the directory can't be watched.
+    async fn watch_if_monitoring(&self, dir: &Path) -> Result<(), Error> {
+        match &mut *self.watcher.lock().await {
+            Some(watcher) => watcher
+                .watch(dir, RecursiveMode::NonRecursive)
+                .map_err(Error::EventError),
+            None => Ok(()),
+        }
+    }
+
+    // read the rotated copies of a registered file right away when the monitoring is running,
+    // otherwise once it starts.
+    async fn catch_up_if_monitoring(&self, path: &Path) {
+        if let Some(sender) = &*self.sender.lock().await {
+            // the monitoring may have stopped
+            let _ = sender.send(Message::CatchUp(path.to_path_buf()));
+        }
+    }
+
+    fn make_registration<F, Fut>(
+        &self,
+        callback: F,
+        options: RegisterOptions,
+    ) -> Result<Registration, Error>
+    where
+        F: Fn(LogEvent) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + Sync + 'static,
+    {
+        let callback = Arc::new(
+            move |log_event: LogEvent| -> Pin<Box<dyn Future<Output = ()> + Send + Sync>> {
+                Box::pin(callback(log_event))
+            },
+        );
+        let patterns = options
+            .patterns
+            .map(|patterns| Filter::any(patterns.iter().map(String::as_str).collect()))
+            .transpose()?;
+        let filter = match (patterns, options.filter) {
+            (Some(patterns), Some(filter)) => Some(patterns.and(filter)),
+            (patterns, filter) => patterns.or(filter),
+        };
+        Ok(Registration {
+            id: SubscriptionId(self.next_id.fetch_add(1, Ordering::Relaxed)),
+            callback,
+            filter,
+            drain_rotated: options.drain_rotated,
+            offset_store: options.offset_store,
+            checkpoint_name: options.checkpoint_name,
+            start_position: options.start_position,
+            multiline: options.multiline,
+            parser: options.parser,
+            #[cfg(feature = "json")]
+            json: options.json,
+            encoding: options.encoding,
+            framing: options.framing,
+            partial_flush: options.partial_flush,
+            batch: None,
+            from_pattern: false,
+        })
+    }
+
+    pub(crate) async fn prepare_monitoring(
+        &self,
+        poll_interval: std::time::Duration,
+    ) -> Result<(rt::UnboundedSender<Message>, EventLoop), Error> {
+        // bridge the notify events into an async channel so that waiting for the next event
+        // doesn't block the executor thread
+        let (sender, receiver) = rt::unbounded();
+        let event_sender = sender.clone();
+        let event_handler = move |event| {
+            let _ = event_sender.send(Message::Event(event));
+        };
+
+        let config = notify::Config::default().with_poll_interval(poll_interval);
+
+        let watcher: RecommendedWatcher =
+            Watcher::new(event_handler, config).map_err(Error::EventError)?;
+        *self.watcher.lock().await = Some(watcher);
+        *self.sender.lock().await = Some(sender.clone());
+
+        // watch the directories instead of the files, so that the files can be rotated or
+        // created after the monitoring has started
+        let mut dirs: HashSet<PathBuf> = self
+            .log_callbacks
+            .lock()
+            .await
+            .keys()
+            .map(|path| watch_dir(path).to_path_buf())
+            .collect();
+        for (file_pattern, _) in self.file_patterns.lock().await.iter() {
+            dirs.insert(file_pattern.dir().to_path_buf());
+        }
+        for dir in dirs {
+            self.watcher
+                .lock()
+                .await
+                .as_mut()
+                .unwrap()
+                .watch(&dir, RecursiveMode::NonRecursive)
+                .map_err(Error::EventError)?;
+        }
+
+        let event_loop = EventLoop {
+            shared: Shared {
+                log_callbacks: Arc::clone(&self.log_callbacks),
+                file_patterns: Arc::clone(&self.file_patterns),
+                tail_states: Arc::clone(&self.tail_states),
+                sender: sender.clone(),
+                config: self.config.clone(),
+                semaphore: self.config.max_in_flight.map(rt::Semaphore::new),
+            },
+            watcher: Arc::clone(&self.watcher),
+            receiver,
+            workers: HashMap::new(),
+        };
+        Ok((sender, event_loop))
+    }
+}
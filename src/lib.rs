@@ -1,13 +1,43 @@
-use async_std::{fs::File, io::BufReader, prelude::*, sync::Mutex, task};
-
-use notify::event::{DataChange, ModifyKind};
-use notify::{event::EventKind, RecommendedWatcher, RecursiveMode, Watcher};
-use regex::RegexSet;
-use shellexpand::tilde;
-use std::collections::HashMap;
+mod archive;
+mod batch;
+mod encoding;
+mod filter;
+mod framing;
+mod handle;
+mod monitor;
+mod multiline;
+mod offset;
+mod parser;
+mod pattern;
+mod queue;
+mod rt;
+mod stream;
+mod tail;
+
+pub use encoding::Encoding;
+pub use filter::Filter;
+pub use framing::Framing;
+pub use handle::LogWatcherHandle;
+pub use monitor::MonitorHandle;
+pub use multiline::Multiline;
+pub use offset::{
+    Checkpoint, CheckpointKey, FileOffsetStore, Fingerprint, MemoryOffsetStore, OffsetStore,
+};
+pub use parser::{
+    CommonLogParser, FieldValue, Fields, LineParser, LogfmtParser, ParseError, SyslogParser,
+};
+pub use pattern::FilePattern;
+pub use stream::LogStream;
+
+use batch::Batch;
+use filter::Matches;
+use notify::{RecommendedWatcher, Watcher};
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
 use std::path::{Path, PathBuf};
 use std::pin::Pin;
-use std::sync::{mpsc::channel, Arc};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 
 //==== Errors
 
@@ -17,6 +47,19 @@ pub enum ErrorKind {
     FileOpenError(std::io::Error),
     #[error("failed to seek file - {0}")]
     FileSeekError(std::io::Error),
+    #[error("failed to watch file - {0}")]
+    WatchError(notify::Error),
+    #[error("failed to load or save offset - {0}")]
+    OffsetStoreError(std::io::Error),
+    #[error("failed to parse line - {0}")]
+    LineParseError(ParseError),
+    #[error("invalid UTF-8 line - {0}")]
+    InvalidUtf8Error(std::str::Utf8Error),
+    #[error("invalid record - {0}")]
+    FrameError(std::io::Error),
+    #[cfg(feature = "json")]
+    #[error("failed to parse json - {0}")]
+    JsonParseError(serde_json::Error),
 }
 
 #[derive(Debug)]
@@ -37,6 +80,13 @@ impl LogError {
                 // format!("{:?} - {}", err, self.path)
                 format!("{:?}", err)
             }
+            ErrorKind::WatchError(err) => format!("{:?}", err),
+            ErrorKind::OffsetStoreError(err) => format!("{:?}", err),
+            ErrorKind::LineParseError(err) => format!("{:?}", err),
+            ErrorKind::InvalidUtf8Error(err) => format!("{:?}", err),
+            ErrorKind::FrameError(err) => format!("{:?}", err),
+            #[cfg(feature = "json")]
+            ErrorKind::JsonParseError(err) => format!("{:?}", err),
         }
     }
 }
@@ -51,32 +101,84 @@ impl std::fmt::Display for LogError {
 pub enum Error {
     #[error("event error - {0}")]
     EventError(notify::Error),
-    #[error("failed to receive data - {0}")]
-    RecvError(std::sync::mpsc::RecvError),
+    #[error("failed to receive data")]
+    RecvError,
+    #[error("invalid pattern - {0}")]
+    InvalidPattern(String),
+    #[error("invalid path - {0:?}")]
+    InvalidPath(PathBuf),
+    #[error("invalid framing - {0}")]
+    InvalidFraming(String),
+    #[error("current directory unavailable - {0}")]
+    CurrentDirUnavailable(std::io::Error),
 }
 
 //==== Events
+
+// Rotation of a watched file, notified to the callback as a distinct event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rotation {
+    // the file has been truncated in place, reading restarts from the beginning.
+    Truncated,
+    // the file has been renamed or removed.
+    Removed,
+    // a new file has been created at the path, it is read from the beginning.
+    Created,
+}
+
 pub struct LogEvent {
     line: Option<String>,
+    bytes: Option<Vec<u8>>,
     log_error: Option<LogError>,
-    path: String,
+    rotation: Option<Rotation>,
+    partial: bool,
+    archive: Option<PathBuf>,
+    dropped: Option<u64>,
+    path: PathBuf,
+    fields: Option<Fields>,
+    matches: Matches,
+    #[cfg(feature = "json")]
+    json: Option<serde_json::Value>,
     // log_watcher: Arc<Mutex<LogWatcher>>,
 }
 
 impl LogEvent {
     fn new(
-        path: String,
+        path: PathBuf,
         line: Option<String>,
         error: Option<LogError>, /*, log_watcher:Arc<Mutex<LogWatcher>>*/
     ) -> Self {
         Self {
             path,
             line,
+            bytes: None,
             log_error: error,
+            rotation: None,
+            partial: false,
+            archive: None,
+            dropped: None,
+            fields: None,
+            matches: Matches::default(),
+            #[cfg(feature = "json")]
+            json: None,
             // log_watcher
         }
     }
 
+    fn new_rotation(path: PathBuf, rotation: Rotation) -> Self {
+        Self {
+            rotation: Some(rotation),
+            ..Self::new(path, None, None)
+        }
+    }
+
+    fn new_dropped(path: PathBuf, dropped: u64) -> Self {
+        Self {
+            dropped: Some(dropped),
+            ..Self::new(path, None, None)
+        }
+    }
+
     // pub async fn change_file_path(&self, new_path: &str) -> Result<(), Error>{
     //     self.log_watcher.lock().await.change_file_path(&self.path, new_path).await
     // }
@@ -84,17 +186,322 @@ impl LogEvent {
     // pub async fn stop_monitoring_file(&self) -> Result<(), Error>{
     //     self.log_watcher.lock().await.stop_monitoring_file(&self.path).await
     // }
-    pub fn file_path(&self) -> &str {
-        self.path.as_str()
+    pub fn file_path(&self) -> &Path {
+        &self.path
     }
 
     pub fn get_line(&self) -> Option<&String> {
         self.line.as_ref()
     }
 
+    // the bytes of the line. the raw bytes for `Encoding::Raw` or a line which isn't valid
+    // UTF-8, the UTF-8 bytes of the decoded line otherwise.
+    pub fn bytes(&self) -> Option<&[u8]> {
+        match &self.bytes {
+            Some(bytes) => Some(bytes),
+            None => self.line.as_ref().map(String::as_bytes),
+        }
+    }
+
     pub fn get_log_error(&self) -> Option<&LogError> {
         self.log_error.as_ref()
     }
+
+    pub fn get_rotation(&self) -> Option<Rotation> {
+        self.rotation
+    }
+
+    // whether the line is the incomplete end of the file, delivered after the partial flush
+    // timeout. the rest of the line is delivered as a new line.
+    pub fn is_partial(&self) -> bool {
+        self.partial
+    }
+
+    // the number of lines dropped by the overflow policy of the `WatcherConfig` since the
+    // previous event, notified before the next event of the registration.
+    pub fn dropped(&self) -> Option<u64> {
+        self.dropped
+    }
+
+    // the rotated copy of the file the line has been read from, e.g. `app.log.1.gz`, when the
+    // lines missed during a rotation are caught up.
+    pub fn archive(&self) -> Option<&Path> {
+        self.archive.as_deref()
+    }
+
+    // the fields of the line, for a registration with `with_parser`.
+    pub fn fields(&self) -> Option<&Fields> {
+        self.fields.as_ref()
+    }
+
+    pub fn field(&self, name: &str) -> Option<&FieldValue> {
+        self.fields.as_ref()?.get(name)
+    }
+
+    // the indices of the filter patterns which matched the line, e.g. the indices of the
+    // `with_patterns` patterns.
+    pub fn matched_patterns(&self) -> &[usize] {
+        &self.matches.patterns
+    }
+
+    // the names of the filters (`Filter::named`) which matched the line.
+    pub fn matched_names(&self) -> &[String] {
+        &self.matches.names
+    }
+
+    // the named capture groups of the matched regular expressions.
+    pub fn captures(&self) -> &BTreeMap<String, String> {
+        &self.matches.captures
+    }
+
+    pub fn capture(&self, name: &str) -> Option<&str> {
+        self.matches.captures.get(name).map(String::as_str)
+    }
+
+    // the line parsed as json, for a registration with `with_json`.
+    #[cfg(feature = "json")]
+    pub fn json(&self) -> Option<&serde_json::Value> {
+        self.json.as_ref()
+    }
+
+    // deserialize the line (or the parsed json) into a type.
+    #[cfg(feature = "json")]
+    pub fn parse<T: serde::de::DeserializeOwned>(&self) -> serde_json::Result<T> {
+        match &self.json {
+            Some(json) => T::deserialize(json),
+            None => serde_json::from_str(self.line.as_deref().unwrap_or_default()),
+        }
+    }
+}
+
+//==== Options
+
+// Where the reading of an existing file starts, resolved when the file is registered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StartPosition {
+    // read the whole file.
+    Beginning,
+    // only the lines appended after the registration.
+    #[default]
+    End,
+    // the last `n` complete lines.
+    LastLines(usize),
+    // a byte offset, limited to the length of the file.
+    Offset(u64),
+    // the first line whose leading RFC 3339 timestamp is at or after the time.
+    Since(SystemTime),
+}
+
+// Options of a registered file.
+#[derive(Debug, Clone)]
+pub struct RegisterOptions {
+    patterns: Option<Vec<String>>,
+    filter: Option<Filter>,
+    drain_rotated: bool,
+    stream_capacity: usize,
+    offset_store: Option<Arc<dyn OffsetStore>>,
+    checkpoint_name: String,
+    start_position: StartPosition,
+    multiline: Option<Multiline>,
+    parser: Option<Arc<dyn LineParser>>,
+    #[cfg(feature = "json")]
+    json: bool,
+    encoding: Encoding,
+    framing: Framing,
+    partial_flush: Option<Duration>,
+}
+
+impl Default for RegisterOptions {
+    fn default() -> Self {
+        Self {
+            patterns: None,
+            filter: None,
+            drain_rotated: true,
+            stream_capacity: stream::DEFAULT_STREAM_CAPACITY,
+            offset_store: None,
+            checkpoint_name: String::new(),
+            start_position: StartPosition::End,
+            multiline: None,
+            parser: None,
+            #[cfg(feature = "json")]
+            json: false,
+            encoding: Encoding::Utf8,
+            framing: Framing::newline(),
+            partial_flush: None,
+        }
+    }
+}
+
+impl RegisterOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // only notify the lines matching any of the patterns.
+    pub fn with_patterns(mut self, patterns: Vec<&str>) -> Self {
+        self.patterns = Some(patterns.into_iter().map(str::to_owned).collect());
+        self
+    }
+
+    // only notify the lines (or records) matching the filter. combined with the patterns if
+    // both are set.
+    pub fn with_filter(mut self, filter: Filter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    // read the rest of a renamed or removed file before switching to the new file. (default: true)
+    pub fn with_drain_rotated(mut self, drain_rotated: bool) -> Self {
+        self.drain_rotated = drain_rotated;
+        self
+    }
+
+    // number of events buffered by a stream. (default: 1024)
+    pub fn with_stream_capacity(mut self, stream_capacity: usize) -> Self {
+        self.stream_capacity = stream_capacity;
+        self
+    }
+
+    // resume from the offset saved in the store, and save the offset after the callbacks.
+    // the offset is saved under the file and `name`, which must stay the same across the runs
+    // and be unique among the registrations of the file sharing the store.
+    pub fn with_offset_store(mut self, offset_store: Arc<dyn OffsetStore>, name: &str) -> Self {
+        self.offset_store = Some(offset_store);
+        self.checkpoint_name = name.to_owned();
+        self
+    }
+
+    // where the reading of an existing file starts. a file created later is read from the
+    // beginning, and a saved offset takes precedence. (default: End)
+    pub fn with_start_position(mut self, start_position: StartPosition) -> Self {
+        self.start_position = start_position;
+        self
+    }
+
+    // assemble the lines of a record spanning several lines into one event.
+    pub fn with_multiline(mut self, multiline: Multiline) -> Self {
+        self.multiline = Some(multiline);
+        self
+    }
+
+    // parse every line into fields, available by `LogEvent::fields`. a line which can't be
+    // parsed is notified with the line and a `LineParseError`.
+    pub fn with_parser<P: LineParser + 'static>(mut self, parser: P) -> Self {
+        self.parser = Some(Arc::new(parser));
+        self
+    }
+
+    // parse every line as json, available by `LogEvent::json`. a line which isn't valid json is
+    // notified with the line and a `JsonParseError`. (default: false)
+    #[cfg(feature = "json")]
+    pub fn with_json(mut self, json: bool) -> Self {
+        self.json = json;
+        self
+    }
+
+    // how the bytes of the lines are decoded. (default: Utf8)
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    // how the content is split into records, e.g. NUL-delimited or length-prefixed records.
+    // (default: newline)
+    pub fn with_framing(mut self, framing: Framing) -> Self {
+        self.framing = framing;
+        self
+    }
+
+    // deliver the incomplete line at the end of the file once it hasn't grown for the timeout,
+    // e.g. the last message of a crashed process. (default: never)
+    pub fn with_partial_flush(mut self, timeout: Duration) -> Self {
+        self.partial_flush = Some(timeout);
+        self
+    }
+}
+
+// How the events of a registration are delivered to its callback.
+//
+// a file is read by a worker of the file, which queues the events to every registration of the
+// file. the callbacks of a registration are run by a task of the registration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Delivery {
+    // the callbacks run one after the other, in the order of the lines.
+    #[default]
+    Ordered,
+    // every callback runs in its own task, the callbacks may complete out of order.
+    Concurrent,
+}
+
+// What happens to a new line when the queue of its registration is full. the other events,
+// e.g. a rotation or an error, are always queued.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Overflow {
+    // the reading of the file waits until a callback takes an event. the other files go on.
+    #[default]
+    Block,
+    // the oldest queued line is dropped.
+    DropOldest,
+    // the new line is dropped.
+    DropNewest,
+    // the new line is dropped if it repeats the last queued line, e.g. the same error logged in
+    // a loop. otherwise the reading waits like `Block`.
+    Coalesce,
+}
+
+// default number of events queued for a registration.
+const DEFAULT_QUEUE_CAPACITY: usize = 1024;
+
+// Options of a `LogWatcher`, shared by all its files.
+#[derive(Debug, Clone)]
+pub struct WatcherConfig {
+    delivery: Delivery,
+    max_in_flight: Option<usize>,
+    queue_capacity: usize,
+    overflow: Overflow,
+}
+
+impl Default for WatcherConfig {
+    fn default() -> Self {
+        Self {
+            delivery: Delivery::Ordered,
+            max_in_flight: None,
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            overflow: Overflow::Block,
+        }
+    }
+}
+
+impl WatcherConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // how the events of a file are delivered. (default: Ordered)
+    pub fn with_delivery(mut self, delivery: Delivery) -> Self {
+        self.delivery = delivery;
+        self
+    }
+
+    // maximum number of events whose callbacks run at once, over all the files. the other
+    // events wait for their turn. (default: unlimited)
+    pub fn with_max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = Some(max_in_flight.max(1));
+        self
+    }
+
+    // number of events queued for a registration, waiting for their callbacks. (default: 1024)
+    pub fn with_queue_capacity(mut self, queue_capacity: usize) -> Self {
+        self.queue_capacity = queue_capacity;
+        self
+    }
+
+    // what happens to a line when the queue of its registration is full. the number of dropped
+    // lines is notified by `LogEvent::dropped`. (default: Block)
+    pub fn with_overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
 }
 
 //==== Callback
@@ -102,68 +509,81 @@ impl LogEvent {
 pub type LogCallback =
     Arc<dyn Fn(LogEvent) -> Pin<Box<dyn Future<Output = ()> + Send + Sync>> + Send + Sync>;
 
+pub type BatchCallback =
+    Arc<dyn Fn(Vec<LogEvent>) -> Pin<Box<dyn Future<Output = ()> + Send + Sync>> + Send + Sync>;
+
+// Identifier of a registration, used to unsubscribe it. the identifiers are given in the order
+// of the registrations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(u64);
+
+#[derive(Clone)]
+struct Registration {
+    id: SubscriptionId,
+    callback: LogCallback,
+    filter: Option<Filter>,
+    drain_rotated: bool,
+    offset_store: Option<Arc<dyn OffsetStore>>,
+    checkpoint_name: String,
+    start_position: StartPosition,
+    multiline: Option<Multiline>,
+    parser: Option<Arc<dyn LineParser>>,
+    #[cfg(feature = "json")]
+    json: bool,
+    encoding: Encoding,
+    framing: Framing,
+    partial_flush: Option<Duration>,
+    // the events are delivered in batches, for `register_batch`.
+    batch: Option<Arc<Batch>>,
+    // registered by a file pattern, stops when the file is removed.
+    from_pattern: bool,
+}
+
+// several registrations of the same file are notified independently.
+type LogCallbacks = HashMap<PathBuf, Vec<Registration>>;
+type FilePatterns = Vec<(FilePattern, Registration)>;
+
 pub struct LogWatcher {
-    log_callbacks: Arc<Mutex<HashMap<String, (LogCallback, Option<RegexSet>)>>>,
-    watcher: Arc<Mutex<Option<RecommendedWatcher>>>,
+    handle: LogWatcherHandle,
+}
+
+impl Default for LogWatcher {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl LogWatcher {
     pub fn new() -> Self {
-        Self {
-            log_callbacks: Arc::new(Mutex::new(HashMap::new())),
-            watcher: Arc::new(Mutex::new(None)),
-        }
+        Self::with_config(WatcherConfig::default())
     }
 
-    pub async fn change_file_path(&mut self, old_path: &str, new_path: &str) -> Result<(), Error> {
-        // change into absolute path
-        let old_path = self.make_absolute_path(&Path::new(old_path));
-        let old_path = old_path.into_os_string().into_string().unwrap();
-
-        let callback = self.log_callbacks.lock().await.remove(&old_path);
-        if let Some(callback) = callback {
-            self.log_callbacks
-                .lock()
-                .await
-                .insert(new_path.to_owned(), callback);
-            let mut watcher = self.watcher.lock().await;
-            if let Some(watcher) = &mut *watcher {
-                watcher
-                    .unwatch(Path::new(&old_path))
-                    .map_err(|e| Error::EventError(e))?;
-                watcher
-                    .watch(Path::new(new_path), RecursiveMode::NonRecursive)
-                    .map_err(|e| Error::EventError(e))?;
-            }
+    pub fn with_config(config: WatcherConfig) -> Self {
+        Self {
+            handle: LogWatcherHandle::new(config),
         }
-        Ok(())
     }
 
-    pub async fn stop_monitoring_file(&mut self, path: &str) -> Result<(), Error> {
-        // change into absolute path
-        let path = self.make_absolute_path(&Path::new(path));
-        let path = path.into_os_string().into_string().unwrap();
+    // a cloneable handle to register, stop and change the files while monitoring.
+    pub fn handle(&self) -> LogWatcherHandle {
+        self.handle.clone()
+    }
 
-        self.log_callbacks.lock().await.remove(&path);
-        let mut watcher = self.watcher.lock().await;
-        if let Some(watcher) = &mut *watcher {
-            watcher
-                .unwatch(Path::new(&path))
-                .map_err(|e| Error::EventError(e))?;
-        }
-        Ok(())
+    pub async fn change_file_path<P: AsRef<Path>, Q: AsRef<Path>>(
+        &mut self,
+        old_path: P,
+        new_path: Q,
+    ) -> Result<(), Error> {
+        self.handle.change_file_path(old_path, new_path).await
     }
 
-    // helper function to convert a relative path into an absolute path
-    fn make_absolute_path(&self, path: &Path) -> PathBuf {
-        let expanded_path = tilde(&path.to_string_lossy()).into_owned();
-        let expanded_path = Path::new(&expanded_path);
+    pub async fn stop_monitoring_file<P: AsRef<Path>>(&mut self, path: P) -> Result<(), Error> {
+        self.handle.stop_monitoring_file(path).await
+    }
 
-        if expanded_path.is_absolute() {
-            expanded_path.to_path_buf()
-        } else {
-            std::env::current_dir().unwrap().join(expanded_path)
-        }
+    // remove a registration. the files registered by a file pattern are removed with it.
+    pub async fn unsubscribe(&mut self, id: SubscriptionId) -> Result<(), Error> {
+        self.handle.unsubscribe(id).await
     }
 
     // register a file path and its associated callback function.
@@ -172,224 +592,180 @@ impl LogWatcher {
         path: P,
         callback: F,
         patterns: Option<Vec<&str>>,
-    ) where
+    ) -> Result<SubscriptionId, Error>
+    where
         F: Fn(LogEvent) -> Fut + Send + Sync + 'static,
-        Fut: std::future::Future<Output = ()> + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + Sync + 'static,
     {
-        let path = self.make_absolute_path(path.as_ref());
-        let path = path.into_os_string().into_string().unwrap();
-
-        let callback = Arc::new(
-            move |log_event: LogEvent| -> Pin<Box<dyn Future<Output = ()> + Send + Sync>> {
-                Box::pin(callback(log_event))
-            },
-        );
-        let regex_set = if let Some(patterns) = patterns {
-            Some(RegexSet::new(patterns).unwrap())
-        } else {
-            None
-        };
-        self.log_callbacks
-            .lock()
-            .await
-            .insert(path, (callback, regex_set));
+        self.handle.register(path, callback, patterns).await
     }
 
-    // Start monitoring
-    pub async fn monitoring(&self, poll_interval: std::time::Duration) -> Result<(), Error> {
-        let (tx, rx) = channel();
-
-        let config = notify::Config::default().with_poll_interval(poll_interval);
+    // register a file path and its associated callback function with the options.
+    pub async fn register_with_options<P: AsRef<Path>, F, Fut>(
+        &mut self,
+        path: P,
+        callback: F,
+        options: RegisterOptions,
+    ) -> Result<SubscriptionId, Error>
+    where
+        F: Fn(LogEvent) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + Sync + 'static,
+    {
+        self.handle
+            .register_with_options(path, callback, options)
+            .await
+    }
 
-        let watcher: RecommendedWatcher = Watcher::new(tx, config).unwrap();
-        *self.watcher.lock().await = Some(watcher);
+    // register a file path and receive its events as a stream.
+    pub async fn register_stream<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        patterns: Option<Vec<&str>>,
+    ) -> Result<LogStream, Error> {
+        self.handle.register_stream(path, patterns).await
+    }
 
-        for path in self.log_callbacks.lock().await.keys() {
-            self.watcher
-                .lock()
-                .await
-                .as_mut()
-                .unwrap()
-                .watch(Path::new(&path), RecursiveMode::NonRecursive)
-                .map_err(|e| Error::EventError(e))?;
-        }
+    // register a file path with the options and receive its events as a stream.
+    pub async fn register_stream_with_options<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        options: RegisterOptions,
+    ) -> Result<LogStream, Error> {
+        self.handle
+            .register_stream_with_options(path, options)
+            .await
+    }
 
-        let file_positions = Arc::new(Mutex::new(HashMap::<String, u64>::new()));
-        loop {
-            match rx.recv() {
-                Ok(event) => match event {
-                    Ok(event) => match event.kind {
-                        EventKind::Modify(ModifyKind::Data(DataChange::Any)) => {
-                            let paths = &event.paths;
-                            for path in paths {
-                                let path_str = path.clone().into_os_string().into_string().unwrap();
-
-                                // clone the contianers
-                                let log_callbacks = Arc::clone(&self.log_callbacks);
-                                let file_positions_clone = Arc::clone(&file_positions);
-
-                                task::spawn(async move {
-                                    // to avoid the deadlock
-                                    let log_callbacks = log_callbacks.lock().await;
-
-                                    if let Some((callback, regex_set)) =
-                                        log_callbacks.get(&path_str)
-                                    {
-                                        let callback = Arc::clone(callback);
-
-                                        let mut file_positions = file_positions_clone.lock().await;
-                                        let position = file_positions
-                                            .entry(path_str.clone())
-                                            .or_insert(std::u64::MAX);
-
-                                        // file open
-                                        match File::open(&path_str).await {
-                                            Ok(file) => {
-                                                let mut reader = BufReader::new(file);
-                                                let mut line = String::new();
-
-                                                // need to set initial position
-                                                if *position == std::u64::MAX {
-                                                    *position = find_last_line(&mut reader).await;
-                                                }
-
-                                                // seek from *position
-                                                match reader
-                                                    .seek(std::io::SeekFrom::Start(*position))
-                                                    .await
-                                                {
-                                                    Ok(_) => {
-                                                        // check if a full line has been read
-                                                        if reader
-                                                            .read_line(&mut line)
-                                                            .await
-                                                            .unwrap()
-                                                            > 0
-                                                            && line.ends_with('\n')
-                                                        {
-                                                            *position += line.len() as u64;
-
-                                                            // remove trailing newline character, if present
-                                                            // use the trim_end_matches
-                                                            let line = line
-                                                                .trim_end_matches(|c| {
-                                                                    c == '\n' || c == '\r'
-                                                                })
-                                                                .to_owned();
-
-                                                            let notify = if let Some(regex_set) =
-                                                                regex_set
-                                                            {
-                                                                if regex_set.is_match(&line) {
-                                                                    true
-                                                                } else {
-                                                                    false
-                                                                }
-                                                            } else {
-                                                                true
-                                                            };
-
-                                                            if notify {
-                                                                let event = LogEvent::new(
-                                                                    path_str,
-                                                                    Some(line),
-                                                                    None,
-                                                                );
-                                                                callback(event).await;
-                                                            }
-                                                        }
-                                                    }
-                                                    Err(e) => {
-                                                        let log_error = LogError {
-                                                            kind: ErrorKind::FileSeekError(e),
-                                                        };
-                                                        let event = LogEvent::new(
-                                                            path_str,
-                                                            None,
-                                                            Some(log_error),
-                                                        );
-                                                        callback(event).await;
-                                                    }
-                                                }
-                                            }
-                                            Err(e) => {
-                                                let log_error = LogError {
-                                                    kind: ErrorKind::FileOpenError(e),
-                                                };
-                                                let event =
-                                                    LogEvent::new(path_str, None, Some(log_error));
-                                                callback(event).await;
-                                            }
-                                        }
-                                    }
-                                });
-                            }
-                        }
-                        _ => {}
-                    },
-                    Err(e) => return Err(Error::EventError(e)),
-                },
-                Err(e) => return Err(Error::RecvError(e)),
-            }
-        }
+    // register a file path and a callback receiving the events in batches. a batch is delivered
+    // once it has `max_lines` events, `max_bytes` bytes of lines, or has waited for `max_wait`.
+    pub async fn register_batch<P: AsRef<Path>, F, Fut>(
+        &mut self,
+        path: P,
+        max_lines: usize,
+        max_bytes: usize,
+        max_wait: Duration,
+        callback: F,
+    ) -> Result<SubscriptionId, Error>
+    where
+        F: Fn(Vec<LogEvent>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + Sync + 'static,
+    {
+        self.handle
+            .register_batch(path, max_lines, max_bytes, max_wait, callback)
+            .await
     }
-}
 
-// find the position of last line.
-async fn find_last_line(reader: &mut BufReader<File>) -> u64 {
-    let mut last_line_start = 0;
-    let mut last_line = String::new();
-    let mut current_position = 0;
+    // register a file path and a callback receiving the events in batches, with the options.
+    pub async fn register_batch_with_options<P: AsRef<Path>, F, Fut>(
+        &mut self,
+        path: P,
+        max_lines: usize,
+        max_bytes: usize,
+        max_wait: Duration,
+        callback: F,
+        options: RegisterOptions,
+    ) -> Result<SubscriptionId, Error>
+    where
+        F: Fn(Vec<LogEvent>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + Sync + 'static,
+    {
+        self.handle
+            .register_batch_with_options(path, max_lines, max_bytes, max_wait, callback, options)
+            .await
+    }
 
-    while let Ok(len) = reader.read_line(&mut last_line).await {
-        if len == 0 || !last_line.ends_with('\n') {
-            break;
-        }
-        last_line_start = current_position;
-        current_position += len as u64;
-        last_line.clear();
+    // register the files matching a glob pattern (e.g. "/var/log/app/*.log"), including the
+    // files created after the registration. a removed file stops being monitored.
+    pub async fn register_glob<F, Fut>(
+        &mut self,
+        pattern: &str,
+        callback: F,
+        patterns: Option<Vec<&str>>,
+    ) -> Result<SubscriptionId, Error>
+    where
+        F: Fn(LogEvent) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + Sync + 'static,
+    {
+        self.handle.register_glob(pattern, callback, patterns).await
     }
 
-    last_line_start
-}
-#[cfg(test)]
-mod tests {
+    // register the files in a directory whose name matches a regular expression.
+    pub async fn register_dir<P: AsRef<Path>, F, Fut>(
+        &mut self,
+        dir: P,
+        file_name: &str,
+        callback: F,
+        patterns: Option<Vec<&str>>,
+    ) -> Result<SubscriptionId, Error>
+    where
+        F: Fn(LogEvent) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + Sync + 'static,
+    {
+        self.handle
+            .register_dir(dir, file_name, callback, patterns)
+            .await
+    }
 
-    use super::find_last_line;
-    use async_std::{fs::remove_file, fs::File, io::BufReader, prelude::*};
+    // register the files matching a file pattern with the options.
+    pub async fn register_pattern_with_options<F, Fut>(
+        &mut self,
+        file_pattern: FilePattern,
+        callback: F,
+        options: RegisterOptions,
+    ) -> Result<SubscriptionId, Error>
+    where
+        F: Fn(LogEvent) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + Sync + 'static,
+    {
+        self.handle
+            .register_pattern_with_options(file_pattern, callback, options)
+            .await
+    }
 
-    #[async_std::test]
-    async fn test_find_last_line() {
-        //
-        let filepath = "test-log.txt";
+    // start monitoring in a spawned task. the returned handle stops the monitoring.
+    pub async fn start(&self, poll_interval: std::time::Duration) -> Result<MonitorHandle, Error> {
+        let (sender, event_loop) = self.handle.prepare_monitoring(poll_interval).await?;
+        Ok(MonitorHandle::new(sender, rt::spawn(event_loop.run())))
+    }
 
-        let _ = remove_file(filepath);
+    // Start monitoring
+    pub async fn monitoring(&self, poll_interval: std::time::Duration) -> Result<(), Error> {
+        let (_sender, event_loop) = self.handle.prepare_monitoring(poll_interval).await?;
+        event_loop.run().await
+    }
+}
 
-        let mut file = File::create(filepath).await.unwrap();
+// the directory watched for a file.
+fn watch_dir(path: &Path) -> &Path {
+    path.parent().unwrap_or(path)
+}
 
-        file.write_all(b"0\n").await.unwrap();
-        file.write_all(b"1\n").await.unwrap();
-        file.write_all(b"2\n").await.unwrap();
-        file.write_all(b"3\n").await.unwrap();
-        file.flush().await.unwrap();
+// unwatch the directory of a removed file unless another file in it is still registered.
+fn unwatch_unused_dir(
+    watcher: &mut RecommendedWatcher,
+    log_callbacks: &LogCallbacks,
+    file_patterns: &FilePatterns,
+    dir: &Path,
+) -> Result<(), Error> {
+    let used = log_callbacks.keys().any(|path| watch_dir(path) == dir)
+        || file_patterns
+            .iter()
+            .any(|(file_pattern, _)| file_pattern.dir() == dir);
+    if !used {
+        watcher.unwatch(dir).map_err(Error::EventError)?;
+    }
+    Ok(())
+}
 
-        let ofile = File::open(&filepath).await.unwrap();
-        let mut reader = BufReader::new(ofile);
-        let position = find_last_line(&mut reader).await;
+#[cfg(all(test, feature = "async-std"))]
+mod tests {
 
-        // assert last line position
-        assert_eq!(position, 6);
+    use async_std::{fs::remove_file, fs::File, prelude::*};
 
-        let mut line = String::new();
-        reader
-            .seek(std::io::SeekFrom::Start(position))
-            .await
-            .unwrap();
-        reader.read_line(&mut line).await.unwrap();
-        // assert last line
-        assert_eq!(line, "3\n");
+    use std::path::PathBuf;
 
-        let _ = remove_file(filepath).await; // Remove the file if it exists
+    fn absolute_path(path: &str) -> PathBuf {
+        std::env::current_dir().unwrap().join(path)
     }
 
     #[async_std::test]
@@ -405,8 +781,14 @@ mod tests {
         let mut file_2 = File::create(log_file_2).await.unwrap();
         let mut file_3 = File::create(log_file_3).await.unwrap();
 
-        log_watcher.register(log_file_1, |_| async {}, None).await;
-        log_watcher.register(log_file_2, |_| async {}, None).await;
+        log_watcher
+            .register(log_file_1, |_| async {}, None)
+            .await
+            .unwrap();
+        log_watcher
+            .register(log_file_2, |_| async {}, None)
+            .await
+            .unwrap();
 
         // write data to log files
         file_1.write_all(b"line 1\n").await.unwrap();
@@ -429,20 +811,23 @@ mod tests {
         file_3.sync_all().await.unwrap();
 
         assert!(!log_watcher
+            .handle
             .log_callbacks
             .lock()
             .await
-            .contains_key(log_file_1));
+            .contains_key(&absolute_path(log_file_1)));
         assert!(!log_watcher
+            .handle
             .log_callbacks
             .lock()
             .await
-            .contains_key(log_file_2));
+            .contains_key(&absolute_path(log_file_2)));
         assert!(log_watcher
+            .handle
             .log_callbacks
             .lock()
             .await
-            .contains_key(log_file_3));
+            .contains_key(&absolute_path(log_file_3)));
 
         // remove the test log files
         remove_file(log_file_1).await.unwrap();
@@ -0,0 +1,538 @@
+use crate::handle::TailStates;
+use crate::queue::{self, Item, QueueSender};
+use crate::tail::{notify_offset_error, TailState};
+use crate::{
+    rt, Delivery, Error, FilePatterns, LogCallback, LogCallbacks, LogEvent, OffsetStore,
+    Registration, SubscriptionId, WatcherConfig,
+};
+
+use notify::event::{EventKind, ModifyKind};
+use notify::{Event, RecommendedWatcher};
+use rt::Mutex;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+pub(crate) enum Message {
+    Event(notify::Result<Event>),
+    // the flush timeout of the incomplete record, the partial line or the batch of a
+    // registration has elapsed.
+    Flush(PathBuf, SubscriptionId),
+    // a file has been registered with lines to read from its rotated copies.
+    CatchUp(PathBuf),
+    // a worker has run a job on a file which isn't monitored anymore.
+    Idle(PathBuf),
+    Shutdown,
+}
+
+// Handle of the monitoring started by `LogWatcher::start`.
+pub struct MonitorHandle {
+    sender: rt::UnboundedSender<Message>,
+    join_handle: rt::JoinHandle<Result<(), Error>>,
+}
+
+impl MonitorHandle {
+    pub(crate) fn new(
+        sender: rt::UnboundedSender<Message>,
+        join_handle: rt::JoinHandle<Result<(), Error>>,
+    ) -> Self {
+        Self {
+            sender,
+            join_handle,
+        }
+    }
+
+    // stop monitoring. waits for the running callbacks to finish and releases the watcher.
+    pub async fn shutdown(self) -> Result<(), Error> {
+        // the monitoring may have stopped with an error already
+        let _ = self.sender.send(Message::Shutdown);
+        self.join_handle.await
+    }
+
+    // wait until the monitoring stops with an error.
+    pub async fn join(self) -> Result<(), Error> {
+        self.join_handle.await
+    }
+}
+
+// Dispatches the file system events to the registered files.
+pub(crate) struct EventLoop {
+    pub(crate) shared: Shared,
+    pub(crate) watcher: Arc<Mutex<Option<RecommendedWatcher>>>,
+    pub(crate) receiver: rt::UnboundedReceiver<Message>,
+    // the pending jobs of the worker of every file with recent events.
+    pub(crate) workers: HashMap<PathBuf, Jobs>,
+}
+
+// The state shared by the tasks reading the files.
+#[derive(Clone)]
+pub(crate) struct Shared {
+    pub(crate) log_callbacks: Arc<Mutex<LogCallbacks>>,
+    pub(crate) file_patterns: Arc<Mutex<FilePatterns>>,
+    pub(crate) tail_states: Arc<Mutex<TailStates>>,
+    // to send the flush messages of the timers
+    pub(crate) sender: rt::UnboundedSender<Message>,
+    pub(crate) config: WatcherConfig,
+    // limits the callbacks running at once, for `WatcherConfig::with_max_in_flight`.
+    pub(crate) semaphore: Option<rt::Semaphore>,
+}
+
+// The queue of the events of a registration, whose callbacks are run by a task of its own.
+pub(crate) type EventSender = QueueSender<LogEvent>;
+
+// Work on a file, run in order by the worker of the file.
+pub(crate) enum Job {
+    // read the file after a file system event. `created` for a file that may be new to the
+    // file patterns.
+    Update { created: bool },
+    // the flush timeout of a registration has elapsed.
+    Flush(SubscriptionId),
+}
+
+impl Job {
+    // merge `other` into `self`, returns false if they can't be merged.
+    fn coalesce(&mut self, other: &Self) -> bool {
+        match (self, other) {
+            (Job::Update { created }, Job::Update { created: other }) => {
+                *created |= *other;
+                true
+            }
+            (Job::Flush(id), Job::Flush(other)) => id == other,
+            _ => false,
+        }
+    }
+}
+
+// The jobs waiting for the worker of a file.
+//
+// a job is merged into a pending job of the same kind, as an update reads the file to the end
+// anyway. so there is at most an update and a flush per registration waiting, and the event
+// loop never waits for a worker.
+pub(crate) struct Jobs {
+    pending: Arc<std::sync::Mutex<Vec<Job>>>,
+    // wakes the worker up when a job is added.
+    ready: rt::Sender<()>,
+}
+
+impl Jobs {
+    fn send(&self, job: Job) {
+        let mut pending = self.pending.lock().unwrap();
+        if !pending.iter_mut().any(|pending| pending.coalesce(&job)) {
+            pending.push(job);
+        }
+        drop(pending);
+        // the worker is already woken up if the channel is full
+        let _ = self.ready.try_send(());
+    }
+}
+
+// the lines can be dropped by the overflow policy, the other events are always delivered.
+impl Item for LogEvent {
+    fn is_droppable(&self) -> bool {
+        self.bytes().is_some()
+    }
+
+    fn repeats(&self, other: &Self) -> bool {
+        self.bytes().is_some() && self.bytes() == other.bytes()
+    }
+}
+
+impl EventLoop {
+    pub(crate) async fn run(mut self) -> Result<(), Error> {
+        // every worker, and every task running callbacks, holds a sender, so the receiver is
+        // closed once all of them are done.
+        let (workers, mut workers_done) = rt::bounded::<()>(1);
+        let (in_flight, mut in_flight_done) = rt::bounded::<()>(1);
+
+        // read the rotated copies of the files registered before the start
+        for path in self.shared.catch_up_paths().await {
+            self.send(path, Job::Update { created: false }, &workers, &in_flight);
+        }
+
+        let result = loop {
+            match self.receiver.recv().await {
+                Some(Message::Event(Ok(event))) => self.dispatch(event, &workers, &in_flight),
+                Some(Message::Flush(path, id)) => {
+                    self.send(path, Job::Flush(id), &workers, &in_flight)
+                }
+                Some(Message::CatchUp(path)) => {
+                    self.send(path, Job::Update { created: false }, &workers, &in_flight)
+                }
+                Some(Message::Idle(path)) => self.remove_idle_worker(path).await,
+                Some(Message::Event(Err(e))) => break Err(Error::EventError(e)),
+                Some(Message::Shutdown) => break Ok(()),
+                None => break Err(Error::RecvError),
+            }
+        };
+
+        // release the watcher, then wait for the workers to finish their pending jobs
+        *self.watcher.lock().await = None;
+        self.workers.clear();
+        drop(workers);
+        workers_done.recv().await;
+
+        // then for the callbacks of the queued events
+        let tail_states: Vec<_> = self
+            .shared
+            .tail_states
+            .lock()
+            .await
+            .values()
+            .cloned()
+            .collect();
+        for tail_state in tail_states {
+            tail_state.lock().await.close_events();
+        }
+        drop(in_flight);
+        in_flight_done.recv().await;
+
+        // deliver the incomplete records and the waiting batches
+        let tail_states = self.shared.tail_states_of(None).await;
+        for (path, registration, tail_state) in &tail_states {
+            let mut tail_state = tail_state.lock().await;
+            tail_state
+                .flush_record(path, registration, Duration::ZERO)
+                .await;
+            tail_state
+                .flush_batch(path, registration, Duration::ZERO)
+                .await;
+        }
+
+        // then save the offsets kept back by the stores
+        let mut flushed: Vec<&Arc<dyn OffsetStore>> = Vec::new();
+        for (path, registration, _) in &tail_states {
+            let Some(offset_store) = &registration.offset_store else {
+                continue;
+            };
+            if flushed
+                .iter()
+                .any(|flushed| Arc::ptr_eq(flushed, offset_store))
+            {
+                continue;
+            }
+            flushed.push(offset_store);
+            let offset_store = Arc::clone(offset_store);
+            if let Err(e) = rt::spawn_blocking(move || offset_store.flush()).await {
+                notify_offset_error(path, &registration.callback, e).await;
+            }
+        }
+
+        result
+    }
+
+    fn dispatch(&mut self, event: Event, workers: &rt::Sender<()>, in_flight: &rt::Sender<()>) {
+        if !matches!(
+            event.kind,
+            EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+        ) {
+            return;
+        }
+
+        // a file that may be new to the file patterns
+        let created = matches!(
+            event.kind,
+            EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(_))
+        );
+
+        for path in event.paths {
+            self.send(path, Job::Update { created }, workers, in_flight);
+        }
+    }
+
+    // add the job to the pending jobs of the worker of the file.
+    fn send(
+        &mut self,
+        path: PathBuf,
+        job: Job,
+        workers: &rt::Sender<()>,
+        in_flight: &rt::Sender<()>,
+    ) {
+        let shared = &self.shared;
+        self.workers
+            .entry(path.clone())
+            .or_insert_with(|| spawn_worker(shared, path, workers, in_flight))
+            .send(job);
+    }
+
+    // remove the worker of a file which isn't monitored anymore. it stops after the jobs
+    // already queued.
+    async fn remove_idle_worker(&mut self, path: PathBuf) {
+        if !self.shared.log_callbacks.lock().await.contains_key(&path) {
+            self.workers.remove(&path);
+        }
+    }
+}
+
+// spawn the task running the jobs of a file one after the other. it reads the file and queues
+// the events to the registrations, their callbacks are run by a task of each registration.
+fn spawn_worker(
+    shared: &Shared,
+    path: PathBuf,
+    workers: &rt::Sender<()>,
+    in_flight: &rt::Sender<()>,
+) -> Jobs {
+    let pending = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let (ready, mut ready_receiver) = rt::bounded(1);
+    let shared = shared.clone();
+    let jobs = Arc::clone(&pending);
+    let worker = workers.clone();
+    let in_flight = in_flight.clone();
+    rt::spawn(async move {
+        let _worker = worker;
+        while ready_receiver.recv().await.is_some() {
+            let jobs = std::mem::take(&mut *jobs.lock().unwrap());
+            for job in jobs {
+                if !shared.run(&path, job, &in_flight).await {
+                    let _ = shared.sender.send(Message::Idle(path.clone()));
+                }
+            }
+        }
+    });
+    Jobs { pending, ready }
+}
+
+impl Shared {
+    async fn acquire(&self) -> Option<rt::Permit> {
+        match &self.semaphore {
+            Some(semaphore) => Some(semaphore.acquire().await),
+            None => None,
+        }
+    }
+
+    // the files whose rotated copies are still to be read.
+    async fn catch_up_paths(&self) -> Vec<PathBuf> {
+        let tail_states: Vec<_> = (self.tail_states.lock().await.iter())
+            .map(|((path, _), tail_state)| (path.clone(), Arc::clone(tail_state)))
+            .collect();
+        let mut paths = Vec::new();
+        for (path, tail_state) in tail_states {
+            if tail_state.lock().await.has_catch_up() && !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+        paths
+    }
+
+    // the registration with its events queued to the task running its callbacks. the task is
+    // spawned the first time the file is read.
+    fn queued(
+        &self,
+        path: &Path,
+        registration: &Registration,
+        tail_state: &mut TailState,
+        in_flight: &rt::Sender<()>,
+    ) -> Registration {
+        let events = tail_state
+            .events_or_insert_with(|| self.spawn_callbacks(path, registration, in_flight));
+        let callback: LogCallback = Arc::new(move |log_event| {
+            let events = Arc::clone(&events);
+            Box::pin(async move { events.send(log_event).await })
+        });
+        Registration {
+            callback,
+            ..registration.clone()
+        }
+    }
+
+    // spawn the task running the callbacks of the queued events of a registration, one after
+    // the other or each in its own task for the concurrent delivery. the dropped lines are
+    // notified before the next event.
+    fn spawn_callbacks(
+        &self,
+        path: &Path,
+        registration: &Registration,
+        in_flight: &rt::Sender<()>,
+    ) -> EventSender {
+        let (sender, mut receiver) = queue::queue(self.config.queue_capacity, self.config.overflow);
+        let shared = self.clone();
+        let path = path.to_path_buf();
+        let registration = registration.clone();
+        let in_flight = in_flight.clone();
+        rt::spawn(async move {
+            while let Some((log_event, dropped, running)) = receiver.recv().await {
+                let permit = shared.acquire().await;
+                if dropped > 0 {
+                    (registration.callback)(LogEvent::new_dropped(path.clone(), dropped)).await;
+                }
+                let callback = shared.callback(&path, &registration, log_event);
+                match shared.config.delivery {
+                    Delivery::Ordered => callback.await,
+                    Delivery::Concurrent => {
+                        let in_flight = in_flight.clone();
+                        rt::spawn(async move {
+                            let _in_flight = in_flight;
+                            let _permit = permit;
+                            let _running = running;
+                            callback.await;
+                        });
+                    }
+                }
+            }
+        });
+        sender
+    }
+
+    // run the callback of an event. the flush timer of a batch starts with its first event.
+    fn callback(
+        &self,
+        path: &Path,
+        registration: &Registration,
+        log_event: LogEvent,
+    ) -> impl std::future::Future<Output = ()> + Send + 'static {
+        let callback = (registration.callback)(log_event);
+        let batch = registration.batch.clone();
+        let message = Message::Flush(path.to_path_buf(), registration.id);
+        let sender = self.sender.clone();
+        async move {
+            callback.await;
+            if let Some(timeout) = batch.and_then(|batch| batch.arm_timer()) {
+                start_flush_timer(&sender, message, timeout);
+            }
+        }
+    }
+
+    // run a job on the file. returns whether the file is still monitored.
+    async fn run(&self, path: &Path, job: Job, in_flight: &rt::Sender<()>) -> bool {
+        match job {
+            Job::Update { created } => self.update(path, created, in_flight).await,
+            Job::Flush(id) => self.flush(path, id, in_flight).await,
+        }
+    }
+
+    // the locks of the registrations and of the reading states are only held to find the
+    // states of the file, so that a slow callback doesn't stall the other files. the reading
+    // state of a registration stays locked while its events are queued.
+    async fn update(&self, path: &Path, created: bool, in_flight: &rt::Sender<()>) -> bool {
+        if created {
+            self.add_matching_file(path).await;
+        }
+
+        let tail_states = self.tail_states_of(Some(path)).await;
+        if tail_states.is_empty() {
+            return false;
+        }
+        // every registration reads the file on its own
+        let mut closed = Vec::new();
+        for (path, registration, tail_state) in tail_states {
+            let mut tail_state = tail_state.lock().await;
+            let queued = self.queued(&path, &registration, &mut tail_state, in_flight);
+            tail_state.update(&path, &queued).await;
+            self.start_flush_timers(&path, &registration, &mut tail_state);
+            if registration.from_pattern && !tail_state.is_open() {
+                closed.push(registration.id);
+            }
+        }
+
+        // stop monitoring a removed file found by a file pattern
+        let mut log_callbacks = self.log_callbacks.lock().await;
+        let mut tail_states = self.tail_states.lock().await;
+        let Some(registrations) = log_callbacks.get_mut(path) else {
+            return false;
+        };
+        registrations.retain(|registration| {
+            if closed.contains(&registration.id) {
+                tail_states.remove(&(path.to_path_buf(), registration.id));
+                return false;
+            }
+            true
+        });
+        if registrations.is_empty() {
+            log_callbacks.remove(path);
+            return false;
+        }
+        true
+    }
+
+    async fn flush(&self, path: &Path, id: SubscriptionId, in_flight: &rt::Sender<()>) -> bool {
+        let tail_states = self.tail_states_of(Some(path)).await;
+        if tail_states.is_empty() {
+            return false;
+        }
+        for (path, registration, tail_state) in tail_states {
+            if registration.id == id {
+                let mut tail_state = tail_state.lock().await;
+                let queued = self.queued(&path, &registration, &mut tail_state, in_flight);
+                tail_state.flush(&path, &queued).await;
+                self.start_flush_timers(&path, &registration, &mut tail_state);
+            }
+        }
+        true
+    }
+
+    // deliver the incomplete record, the partial line or the batch once they have waited for
+    // their timeout. a single timer is pending for the record and the partial line, and one
+    // for the batch.
+    fn start_flush_timers(
+        &self,
+        path: &Path,
+        registration: &Registration,
+        tail_state: &mut TailState,
+    ) {
+        let message = || Message::Flush(path.to_path_buf(), registration.id);
+        if let Some(timeout) = tail_state.arm_flush_timer(registration) {
+            start_flush_timer(&self.sender, message(), timeout);
+        }
+        if let Some(timeout) = registration.batch.as_ref().and_then(|b| b.arm_timer()) {
+            start_flush_timer(&self.sender, message(), timeout);
+        }
+    }
+
+    // start monitoring a new file matching the file patterns.
+    async fn add_matching_file(&self, path: &Path) {
+        // to avoid the deadlock
+        let mut log_callbacks = self.log_callbacks.lock().await;
+        let mut tail_states = self.tail_states.lock().await;
+        let file_patterns = self.file_patterns.lock().await;
+        for (file_pattern, registration) in file_patterns.iter() {
+            if !file_pattern.matches(path) {
+                continue;
+            }
+            let registrations = log_callbacks.entry(path.to_path_buf()).or_default();
+            if registrations.iter().any(|r| r.id == registration.id) {
+                continue;
+            }
+            registrations.push(Registration {
+                from_pattern: true,
+                ..registration.clone()
+            });
+            tail_states.insert((path.to_path_buf(), registration.id), Default::default());
+        }
+    }
+
+    // the registrations of a file, or of every file, with their reading states.
+    async fn tail_states_of(
+        &self,
+        path: Option<&Path>,
+    ) -> Vec<(PathBuf, Registration, Arc<Mutex<TailState>>)> {
+        let log_callbacks = self.log_callbacks.lock().await;
+        let mut tail_states = self.tail_states.lock().await;
+        let files: Vec<_> = match path {
+            Some(path) => log_callbacks.get_key_value(path).into_iter().collect(),
+            None => log_callbacks.iter().collect(),
+        };
+        let mut states = Vec::new();
+        for (file_path, registrations) in files {
+            for registration in registrations {
+                let tail_state = tail_states
+                    .entry((file_path.clone(), registration.id))
+                    .or_default();
+                states.push((
+                    file_path.clone(),
+                    registration.clone(),
+                    Arc::clone(tail_state),
+                ));
+            }
+        }
+        states
+    }
+}
+
+// send the message after the timeout. the monitoring may have stopped by then.
+fn start_flush_timer(sender: &rt::UnboundedSender<Message>, message: Message, timeout: Duration) {
+    let sender = sender.clone();
+    rt::spawn(async move {
+        rt::sleep(timeout).await;
+        let _ = sender.send(message);
+    });
+}
@@ -0,0 +1,172 @@
+use crate::Error;
+
+use regex::bytes::Regex;
+use std::time::{Duration, Instant};
+
+// default idle time before an incomplete record is delivered.
+const DEFAULT_FLUSH_TIMEOUT: Duration = Duration::from_secs(1);
+
+// Assembly of a record spanning several lines, e.g. a stack trace.
+//
+// The lines of a record are joined with '\n' and delivered as the line of a single `LogEvent`.
+// the last record is delivered once no line has been appended for the flush timeout.
+#[derive(Debug, Clone)]
+pub struct Multiline {
+    rule: Rule,
+    flush_timeout: Duration,
+}
+
+#[derive(Debug, Clone)]
+enum Rule {
+    Indented,
+    StartsWith(Regex),
+    EndsWith(Regex),
+}
+
+impl Multiline {
+    // a line starting with a space or a tab continues the previous record.
+    pub fn indented() -> Self {
+        Self::new(Rule::Indented)
+    }
+
+    // a record starts at a line matching the regular expression.
+    pub fn starts_with(pattern: &str) -> Result<Self, Error> {
+        let regex = Regex::new(pattern).map_err(|e| Error::InvalidPattern(e.to_string()))?;
+        Ok(Self::new(Rule::StartsWith(regex)))
+    }
+
+    // a record ends at a line matching the regular expression.
+    pub fn ends_with(pattern: &str) -> Result<Self, Error> {
+        let regex = Regex::new(pattern).map_err(|e| Error::InvalidPattern(e.to_string()))?;
+        Ok(Self::new(Rule::EndsWith(regex)))
+    }
+
+    // idle time before an incomplete record is delivered. (default: 1s)
+    pub fn with_flush_timeout(mut self, flush_timeout: Duration) -> Self {
+        self.flush_timeout = flush_timeout;
+        self
+    }
+
+    pub(crate) fn flush_timeout(&self) -> Duration {
+        self.flush_timeout
+    }
+
+    fn new(rule: Rule) -> Self {
+        Self {
+            rule,
+            flush_timeout: DEFAULT_FLUSH_TIMEOUT,
+        }
+    }
+}
+
+// Lines of the record being assembled.
+#[derive(Default)]
+pub(crate) struct Record {
+    // the lines in UTF-8, or the raw bytes for `Encoding::Raw`.
+    lines: Vec<Vec<u8>>,
+    // offset of the first line in the file.
+    start: u64,
+    updated: Option<Instant>,
+}
+
+impl Record {
+    // add a line starting at `line_start`, returns the record completed by the line.
+    pub(crate) fn push(
+        &mut self,
+        multiline: &Multiline,
+        line: Vec<u8>,
+        line_start: u64,
+    ) -> Option<Vec<u8>> {
+        let starts = match &multiline.rule {
+            Rule::Indented => !line.starts_with(b" ") && !line.starts_with(b"\t"),
+            Rule::StartsWith(regex) => regex.is_match(&line),
+            Rule::EndsWith(_) => false,
+        };
+        let ends = matches!(&multiline.rule, Rule::EndsWith(regex) if regex.is_match(&line));
+
+        let completed = if starts { self.take() } else { None };
+        if self.lines.is_empty() {
+            self.start = line_start;
+        }
+        self.lines.push(line);
+        self.updated = Some(Instant::now());
+
+        if ends {
+            self.take()
+        } else {
+            completed
+        }
+    }
+
+    // the incomplete record, once no line has been added for the timeout.
+    pub(crate) fn take_expired(&mut self, timeout: Duration) -> Option<Vec<u8>> {
+        match self.updated {
+            Some(updated) if updated.elapsed() >= timeout => self.take(),
+            _ => None,
+        }
+    }
+
+    // the time left before the incomplete record expires.
+    pub(crate) fn expires_in(&self, timeout: Duration) -> Option<Duration> {
+        let updated = self.updated?;
+        Some(timeout.saturating_sub(updated.elapsed()))
+    }
+
+    fn take(&mut self) -> Option<Vec<u8>> {
+        if self.lines.is_empty() {
+            return None;
+        }
+        self.updated = None;
+        Some(std::mem::take(&mut self.lines).join(&b'\n'))
+    }
+
+    // offset of the first line of the incomplete record.
+    pub(crate) fn start(&self) -> Option<u64> {
+        (!self.lines.is_empty()).then_some(self.start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Multiline, Record};
+    use std::time::Duration;
+
+    fn assemble(multiline: &Multiline, lines: &[&str]) -> Vec<String> {
+        let mut record = Record::default();
+        let mut records: Vec<Vec<u8>> = lines
+            .iter()
+            .filter_map(|line| record.push(multiline, line.as_bytes().to_vec(), 0))
+            .collect();
+        records.extend(record.take_expired(Duration::ZERO));
+        records
+            .into_iter()
+            .map(|record| String::from_utf8(record).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_multiline() {
+        let lines = [
+            "Exception in thread \"main\"",
+            "    at Main.run",
+            "    at Main.main",
+            "INFO done",
+        ];
+        let records = assemble(&Multiline::indented(), &lines);
+        assert_eq!(
+            records,
+            vec![
+                "Exception in thread \"main\"\n    at Main.run\n    at Main.main",
+                "INFO done",
+            ]
+        );
+
+        let lines = ["[1] begin", "detail", "[2] next"];
+        let records = assemble(&Multiline::starts_with(r"^\[\d+\]").unwrap(), &lines);
+        assert_eq!(records, vec!["[1] begin\ndetail", "[2] next"]);
+
+        let lines = ["a", "b;", "c", "d"];
+        let records = assemble(&Multiline::ends_with(";$").unwrap(), &lines);
+        assert_eq!(records, vec!["a\nb;", "c\nd"]);
+    }
+}
@@ -0,0 +1,363 @@
+use crate::rt::{fs::File, io::*};
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// number of bytes at the beginning of a file used for the fingerprint.
+const FINGERPRINT_LEN: u64 = 256;
+
+// Identity of a file, to tell whether a saved offset still belongs to the file at the path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fingerprint {
+    // device and inode of the file, if supported by the platform.
+    pub file_id: Option<(u64, u64)>,
+    // number of bytes hashed at the beginning of the file.
+    pub head_len: u64,
+    // FNV-1a hash of the first `head_len` bytes.
+    pub head_hash: u64,
+}
+
+// Read offset of a file, saved after the callbacks have returned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub fingerprint: Fingerprint,
+    pub offset: u64,
+}
+
+// Key of a saved offset: the absolute path of the file and the name given to the registration
+// by `RegisterOptions::with_offset_store`, so that the registrations of a file sharing a store
+// keep their own offsets, whatever the order of the registrations.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CheckpointKey {
+    pub path: PathBuf,
+    pub name: String,
+}
+
+// Storage of the read offsets, keyed by the file and the name of the registration.
+//
+// A registration with an offset store resumes from the saved offset when the fingerprint of the
+// file still matches, or reads the file from the beginning when the file has been replaced.
+// the methods are called on a blocking thread, so they may do blocking I/O.
+pub trait OffsetStore: Debug + Send + Sync {
+    fn load(&self, key: &CheckpointKey) -> std::io::Result<Option<Checkpoint>>;
+    fn commit(&self, key: &CheckpointKey, checkpoint: Checkpoint) -> std::io::Result<()>;
+
+    // save the commits kept back by the store, called at the shutdown. (default: nothing)
+    fn flush(&self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+// Offsets kept in memory, e.g. to resume the files after a watcher is restarted.
+#[derive(Debug, Default)]
+pub struct MemoryOffsetStore {
+    checkpoints: Mutex<HashMap<CheckpointKey, Checkpoint>>,
+}
+
+impl MemoryOffsetStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl OffsetStore for MemoryOffsetStore {
+    fn load(&self, key: &CheckpointKey) -> std::io::Result<Option<Checkpoint>> {
+        Ok(self.checkpoints.lock().unwrap().get(key).copied())
+    }
+
+    fn commit(&self, key: &CheckpointKey, checkpoint: Checkpoint) -> std::io::Result<()> {
+        self.checkpoints
+            .lock()
+            .unwrap()
+            .insert(key.clone(), checkpoint);
+        Ok(())
+    }
+}
+
+// default minimum time between two writes of a `FileOffsetStore`.
+const DEFAULT_WRITE_INTERVAL: Duration = Duration::from_secs(1);
+
+// Offsets saved in a JSON file.
+//
+// the file is written at most once per write interval. the commits in between are written by
+// a thread of the store once the interval has elapsed, or by `flush` (at the shutdown, or when
+// the store is dropped). after a crash, the lines of the last interval are delivered again.
+#[derive(Debug)]
+pub struct FileOffsetStore {
+    path: PathBuf,
+    write_interval: Duration,
+    state: Arc<Mutex<FileState>>,
+}
+
+#[derive(Debug)]
+struct FileState {
+    checkpoints: HashMap<CheckpointKey, Checkpoint>,
+    // whether some commits haven't been written yet.
+    dirty: bool,
+    last_write: Option<Instant>,
+    // whether a thread is waiting to write the commits kept back.
+    scheduled: bool,
+}
+
+impl FileOffsetStore {
+    // open the store, the file is created on the first commit.
+    pub fn open<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let checkpoints = match std::fs::read(&path) {
+            Ok(data) => serde_json::from_slice::<Vec<SavedCheckpoint>>(&data)?
+                .into_iter()
+                .map(|saved| (saved.key, saved.checkpoint))
+                .collect(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(Self {
+            path,
+            write_interval: DEFAULT_WRITE_INTERVAL,
+            state: Arc::new(Mutex::new(FileState {
+                checkpoints,
+                dirty: false,
+                last_write: None,
+                scheduled: false,
+            })),
+        })
+    }
+
+    // minimum time between two writes of the file. (default: 1s)
+    pub fn with_write_interval(mut self, write_interval: Duration) -> Self {
+        self.write_interval = write_interval;
+        self
+    }
+
+    // write the commits kept back once the write interval has elapsed. an error is returned
+    // by the next commit or flush, which write them again.
+    fn schedule_write(&self, state: &mut FileState, wait: Duration) {
+        if state.scheduled {
+            return;
+        }
+        state.scheduled = true;
+        let path = self.path.clone();
+        let shared = Arc::clone(&self.state);
+        std::thread::spawn(move || {
+            std::thread::sleep(wait);
+            let mut state = shared.lock().unwrap();
+            state.scheduled = false;
+            if state.dirty {
+                let _ = write(&path, &mut state);
+            }
+        });
+    }
+}
+
+// write a temporary file, flushed to the disk, and rename it, so that a crash never leaves a
+// broken file.
+fn write(path: &Path, state: &mut FileState) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let saved: Vec<_> = (state.checkpoints.iter())
+        .map(|(key, checkpoint)| SavedCheckpoint {
+            key: key.clone(),
+            checkpoint: *checkpoint,
+        })
+        .collect();
+    let data = serde_json::to_vec_pretty(&saved)?;
+    let mut tmp_path = path.to_path_buf().into_os_string();
+    tmp_path.push(".tmp");
+    let mut file = std::fs::File::create(&tmp_path)?;
+    file.write_all(&data)?;
+    file.sync_all()?;
+    std::fs::rename(&tmp_path, path)?;
+
+    state.dirty = false;
+    state.last_write = Some(Instant::now());
+    Ok(())
+}
+
+// an offset in the file of a `FileOffsetStore`.
+#[derive(Serialize, Deserialize)]
+struct SavedCheckpoint {
+    #[serde(flatten)]
+    key: CheckpointKey,
+    checkpoint: Checkpoint,
+}
+
+impl OffsetStore for FileOffsetStore {
+    fn load(&self, key: &CheckpointKey) -> std::io::Result<Option<Checkpoint>> {
+        Ok(self.state.lock().unwrap().checkpoints.get(key).copied())
+    }
+
+    fn commit(&self, key: &CheckpointKey, checkpoint: Checkpoint) -> std::io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.checkpoints.insert(key.clone(), checkpoint);
+        state.dirty = true;
+
+        let written = state.last_write.map(|last_write| last_write.elapsed());
+        if let Some(written) = written.filter(|written| *written < self.write_interval) {
+            self.schedule_write(&mut state, self.write_interval - written);
+            return Ok(());
+        }
+        write(&self.path, &mut state)
+    }
+
+    fn flush(&self) -> std::io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if !state.dirty {
+            return Ok(());
+        }
+        write(&self.path, &mut state)
+    }
+}
+
+impl Drop for FileOffsetStore {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+impl Fingerprint {
+    // fingerprint of an open file, hashing at most `limit` bytes of its beginning.
+    pub(crate) async fn read(
+        reader: &mut BufReader<File>,
+        file_id: Option<(u64, u64)>,
+        limit: u64,
+    ) -> std::io::Result<Self> {
+        let head_len = limit.min(FINGERPRINT_LEN);
+        Ok(Self {
+            file_id,
+            head_len,
+            head_hash: head_hash(reader, head_len).await?,
+        })
+    }
+
+    // whether an open file is the file of the fingerprint.
+    pub(crate) async fn matches(
+        &self,
+        reader: &mut BufReader<File>,
+        file_id: Option<(u64, u64)>,
+    ) -> bool {
+        if self.file_id.is_some() && file_id.is_some() && self.file_id != file_id {
+            return false;
+        }
+        matches!(head_hash(reader, self.head_len).await, Ok(hash) if hash == self.head_hash)
+    }
+
+    // whether the content of another file, e.g. a compressed copy, starts like the file of the
+    // fingerprint.
+    pub(crate) fn matches_head(&self, data: &[u8]) -> bool {
+        data.len() as u64 >= self.head_len
+            && fnv1a(&data[..self.head_len as usize]) == self.head_hash
+    }
+
+    // whether no more bytes can be added to the fingerprint.
+    pub(crate) fn is_full(&self) -> bool {
+        self.head_len == FINGERPRINT_LEN
+    }
+}
+
+async fn head_hash(reader: &mut BufReader<File>, len: u64) -> std::io::Result<u64> {
+    let mut head = vec![0; len as usize];
+    reader.seek(SeekFrom::Start(0)).await?;
+    reader.read_exact(&mut head).await?;
+    Ok(fnv1a(&head))
+}
+
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Checkpoint, CheckpointKey, FileOffsetStore, Fingerprint, OffsetStore};
+    use std::time::Duration;
+
+    #[test]
+    fn test_file_offset_store() {
+        let path = "test-offsets.json";
+        let _ = std::fs::remove_file(path);
+
+        let checkpoint = Checkpoint {
+            fingerprint: Fingerprint {
+                file_id: Some((1, 2)),
+                head_len: 3,
+                head_hash: 4,
+            },
+            offset: 5,
+        };
+
+        // two registrations of the same file
+        let key = |name: &str| CheckpointKey {
+            path: "/var/log/app.log".into(),
+            name: name.to_owned(),
+        };
+        let first = &key("lines");
+        let store = FileOffsetStore::open(path).unwrap();
+        assert_eq!(store.load(first).unwrap(), None);
+        store.commit(first, checkpoint).unwrap();
+        assert_eq!(store.load(&key("batches")).unwrap(), None);
+
+        // reopen the saved offsets
+        let store = FileOffsetStore::open(path)
+            .unwrap()
+            .with_write_interval(Duration::from_secs(3600));
+        assert_eq!(store.load(first).unwrap(), Some(checkpoint));
+
+        // the commits within the write interval are written by the flush
+        let next = Checkpoint {
+            offset: 6,
+            ..checkpoint
+        };
+        store.commit(first, checkpoint).unwrap();
+        store.commit(first, next).unwrap();
+        let saved = FileOffsetStore::open(path).unwrap();
+        assert_eq!(saved.load(first).unwrap(), Some(checkpoint));
+        store.flush().unwrap();
+        let saved = FileOffsetStore::open(path).unwrap();
+        assert_eq!(saved.load(first).unwrap(), Some(next));
+
+        std::fs::remove_file(path).unwrap();
+    }
+    #[test]
+    fn test_file_offset_store_write_interval() {
+        let path = "test-offsets-interval.json";
+        let _ = std::fs::remove_file(path);
+
+        let checkpoint = Checkpoint {
+            fingerprint: Fingerprint {
+                file_id: None,
+                head_len: 1,
+                head_hash: 2,
+            },
+            offset: 3,
+        };
+        let key = CheckpointKey {
+            path: "/var/log/app.log".into(),
+            name: "lines".to_owned(),
+        };
+        let store = FileOffsetStore::open(path)
+            .unwrap()
+            .with_write_interval(Duration::from_millis(200));
+        store.commit(&key, checkpoint).unwrap();
+        let next = Checkpoint {
+            offset: 4,
+            ..checkpoint
+        };
+        store.commit(&key, next).unwrap();
+
+        // the commit kept back is written once the interval has elapsed, without a flush
+        let saved = FileOffsetStore::open(path).unwrap();
+        assert_eq!(saved.load(&key).unwrap(), Some(checkpoint));
+        std::thread::sleep(Duration::from_millis(500));
+        let saved = FileOffsetStore::open(path).unwrap();
+        assert_eq!(saved.load(&key).unwrap(), Some(next));
+
+        drop(store);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,459 @@
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Fields of a parsed line by name.
+pub type Fields = BTreeMap<String, FieldValue>;
+
+// Value of a parsed field.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    String(String),
+    Integer(i64),
+    Timestamp(SystemTime),
+}
+
+impl FieldValue {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            FieldValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            FieldValue::Integer(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_timestamp(&self) -> Option<SystemTime> {
+        match self {
+            FieldValue::Timestamp(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    // an integer if the text is a number, a string otherwise.
+    fn infer(value: &str) -> Self {
+        match value.parse() {
+            Ok(value) => FieldValue::Integer(value),
+            Err(_) => FieldValue::String(value.to_owned()),
+        }
+    }
+}
+
+impl From<&str> for FieldValue {
+    fn from(value: &str) -> Self {
+        FieldValue::String(value.to_owned())
+    }
+}
+
+impl From<String> for FieldValue {
+    fn from(value: String) -> Self {
+        FieldValue::String(value)
+    }
+}
+
+impl From<i64> for FieldValue {
+    fn from(value: i64) -> Self {
+        FieldValue::Integer(value)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+pub struct ParseError(pub String);
+
+// Parser of the lines of a registration into fields, available by `LogEvent::fields`.
+//
+// A line which can't be parsed is notified with the line and a `LineParseError`.
+pub trait LineParser: Debug + Send + Sync {
+    fn parse(&self, line: &str) -> Result<Fields, ParseError>;
+}
+
+//==== logfmt
+
+// `key=value` pairs, the value can be quoted: `level=info msg="request done" status=200`.
+// a key without a value is taken as "true".
+#[derive(Debug, Clone, Default)]
+pub struct LogfmtParser;
+
+impl LineParser for LogfmtParser {
+    fn parse(&self, line: &str) -> Result<Fields, ParseError> {
+        let mut fields = Fields::new();
+        let mut chars = line.chars().peekable();
+
+        loop {
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            if chars.peek().is_none() {
+                break;
+            }
+
+            let key: String =
+                std::iter::from_fn(|| chars.next_if(|c| *c != '=' && !c.is_whitespace())).collect();
+            if key.is_empty() {
+                return Err(ParseError(format!("empty key in logfmt line: {}", line)));
+            }
+            if chars.next_if_eq(&'=').is_none() {
+                fields.insert(key, FieldValue::String("true".to_owned()));
+                continue;
+            }
+
+            let value = if chars.next_if_eq(&'"').is_some() {
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => value.extend(chars.next()),
+                        Some(c) => value.push(c),
+                        None => {
+                            return Err(ParseError(format!(
+                                "unclosed quote in logfmt line: {}",
+                                line
+                            )))
+                        }
+                    }
+                }
+                FieldValue::String(value)
+            } else {
+                let value: String =
+                    std::iter::from_fn(|| chars.next_if(|c| !c.is_whitespace())).collect();
+                FieldValue::infer(&value)
+            };
+            fields.insert(key, value);
+        }
+
+        if fields.is_empty() {
+            return Err(ParseError("empty logfmt line".to_owned()));
+        }
+        Ok(fields)
+    }
+}
+
+//==== syslog
+
+// RFC 5424 (`<34>1 2003-10-11T22:14:15.003Z host app 1234 ID47 - message`) and
+// RFC 3164 (`<34>Oct 11 22:14:15 host app[1234]: message`) syslog lines. the priority is
+// optional for RFC 3164, as written in the files by syslog daemons.
+//
+// fields: facility, severity, version, timestamp, hostname, app_name, proc_id, msg_id,
+// structured_data, message.
+#[derive(Debug, Clone)]
+pub struct SyslogParser {
+    rfc5424: Regex,
+    rfc3164: Regex,
+}
+
+impl Default for SyslogParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SyslogParser {
+    pub fn new() -> Self {
+        Self {
+            rfc5424: Regex::new(
+                r"^<(?P<pri>\d{1,3})>(?P<version>\d{1,2}) (?P<timestamp>\S+) (?P<hostname>\S+) (?P<app_name>\S+) (?P<proc_id>\S+) (?P<msg_id>\S+) (?P<structured_data>-|(?:\[(?:[^\]\\]|\\.)*\])+)(?: (?P<message>.*))?$",
+            )
+            .unwrap(),
+            rfc3164: Regex::new(
+                r"^(?:<(?P<pri>\d{1,3})>)?(?P<timestamp>[A-Z][a-z]{2} [ \d]\d \d{2}:\d{2}:\d{2}) (?P<hostname>\S+) (?P<app_name>[^\[:\s]+)(?:\[(?P<proc_id>[^\]]+)\])?: ?(?P<message>.*)$",
+            )
+            .unwrap(),
+        }
+    }
+}
+
+impl LineParser for SyslogParser {
+    fn parse(&self, line: &str) -> Result<Fields, ParseError> {
+        let captures = self
+            .rfc5424
+            .captures(line)
+            .or_else(|| self.rfc3164.captures(line))
+            .ok_or_else(|| ParseError(format!("not a syslog line: {}", line)))?;
+
+        let mut fields = Fields::new();
+        if let Some(pri) = captures.name("pri") {
+            // the facilities go up to 23
+            let pri: i64 = pri.as_str().parse().unwrap();
+            if pri > 191 {
+                return Err(ParseError(format!("invalid syslog priority: {}", line)));
+            }
+            fields.insert("facility".to_owned(), FieldValue::Integer(pri / 8));
+            fields.insert("severity".to_owned(), FieldValue::Integer(pri % 8));
+        }
+        for name in [
+            "version",
+            "hostname",
+            "app_name",
+            "proc_id",
+            "msg_id",
+            "structured_data",
+        ] {
+            match captures.name(name).map(|value| value.as_str()) {
+                // the nil value of RFC 5424
+                None | Some("-") => {}
+                Some(value) => {
+                    fields.insert(name.to_owned(), FieldValue::infer(value));
+                }
+            }
+        }
+        if let Some(message) = captures.name("message") {
+            fields.insert(
+                "message".to_owned(),
+                FieldValue::String(message.as_str().to_owned()),
+            );
+        }
+
+        let timestamp = &captures["timestamp"];
+        let timestamp = match humantime::parse_rfc3339_weak(timestamp) {
+            Ok(timestamp) => FieldValue::Timestamp(timestamp),
+            Err(_) => FieldValue::String(timestamp.to_owned()),
+        };
+        fields.insert("timestamp".to_owned(), timestamp);
+        Ok(fields)
+    }
+}
+
+//==== common log format
+
+// Common and combined log format of Apache and Nginx:
+// `127.0.0.1 - frank [10/Oct/2000:13:55:36 -0700] "GET /a.gif HTTP/1.0" 200 2326 "referer" "agent"`
+//
+// fields: remote_host, ident, user, timestamp, request, method, path, protocol, status, bytes,
+// referer, user_agent. the fields with "-" are left out.
+#[derive(Debug, Clone)]
+pub struct CommonLogParser {
+    regex: Regex,
+}
+
+impl Default for CommonLogParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CommonLogParser {
+    pub fn new() -> Self {
+        Self {
+            regex: Regex::new(
+                r#"^(?P<remote_host>\S+) (?P<ident>\S+) (?P<user>\S+) \[(?P<timestamp>[^\]]+)\] "(?P<request>(?:[^"\\]|\\.)*)" (?P<status>\d{3}|-) (?P<bytes>\d+|-)(?: "(?P<referer>(?:[^"\\]|\\.)*)" "(?P<user_agent>(?:[^"\\]|\\.)*)")?"#,
+            )
+            .unwrap(),
+        }
+    }
+}
+
+impl LineParser for CommonLogParser {
+    fn parse(&self, line: &str) -> Result<Fields, ParseError> {
+        let captures = self
+            .regex
+            .captures(line)
+            .ok_or_else(|| ParseError(format!("not a common log format line: {}", line)))?;
+
+        let mut fields = Fields::new();
+        for name in [
+            "remote_host",
+            "ident",
+            "user",
+            "request",
+            "referer",
+            "user_agent",
+        ] {
+            match captures.name(name).map(|value| value.as_str()) {
+                None | Some("-") => {}
+                Some(value) => {
+                    fields.insert(name.to_owned(), FieldValue::String(value.to_owned()));
+                }
+            }
+        }
+        for name in ["status", "bytes"] {
+            if let Ok(value) = captures[name].parse() {
+                fields.insert(name.to_owned(), FieldValue::Integer(value));
+            }
+        }
+
+        // "GET /index.html HTTP/1.1"
+        let mut request = captures["request"].splitn(3, ' ');
+        if let (Some(method), Some(path), Some(protocol)) =
+            (request.next(), request.next(), request.next())
+        {
+            fields.insert("method".to_owned(), FieldValue::String(method.to_owned()));
+            fields.insert("path".to_owned(), FieldValue::String(path.to_owned()));
+            fields.insert(
+                "protocol".to_owned(),
+                FieldValue::String(protocol.to_owned()),
+            );
+        }
+
+        let timestamp = &captures["timestamp"];
+        let timestamp = match parse_clf_timestamp(timestamp) {
+            Some(timestamp) => FieldValue::Timestamp(timestamp),
+            None => FieldValue::String(timestamp.to_owned()),
+        };
+        fields.insert("timestamp".to_owned(), timestamp);
+        Ok(fields)
+    }
+}
+
+// "10/Oct/2000:13:55:36 -0700"
+fn parse_clf_timestamp(timestamp: &str) -> Option<SystemTime> {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let (date_time, offset) = timestamp.split_once(' ')?;
+    let mut parts = date_time.splitn(4, ':');
+    let date = parts.next()?;
+    let hour: u64 = parts.next()?.parse().ok().filter(|hour| *hour < 24)?;
+    let minute: u64 = parts.next()?.parse().ok().filter(|minute| *minute < 60)?;
+    // up to 60 for a leap second
+    let second: u64 = parts.next()?.parse().ok().filter(|second| *second <= 60)?;
+
+    let mut date = date.splitn(3, '/');
+    let day: u64 = date
+        .next()?
+        .parse()
+        .ok()
+        .filter(|day| (1..=31).contains(day))?;
+    let month = date.next()?;
+    let month = MONTHS.iter().position(|name| *name == month)? as u64 + 1;
+    let year: i64 = date
+        .next()?
+        .parse()
+        .ok()
+        .filter(|year| (0..=9999).contains(year))?;
+
+    // "+0900" or "-0700"
+    let sign = match offset.get(..1)? {
+        "+" => 1,
+        "-" => -1,
+        _ => return None,
+    };
+    let offset_hours: i64 = offset.get(1..3)?.parse().ok()?;
+    let offset_minutes: i64 = offset
+        .get(3..5)?
+        .parse()
+        .ok()
+        .filter(|minutes| *minutes < 60)?;
+    let offset = sign * (offset_hours * 3600 + offset_minutes * 60);
+
+    // the fields are in range, but the timestamp may still be before the epoch
+    let seconds = days_from_civil(year, month, day)
+        .checked_mul(86400)?
+        .checked_add((hour * 3600 + minute * 60 + second) as i64)?
+        .checked_sub(offset)?;
+    UNIX_EPOCH.checked_add(Duration::from_secs(u64::try_from(seconds).ok()?))
+}
+
+// days since 1970-01-01 of a date in the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: u64, day: u64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CommonLogParser, FieldValue, LineParser, LogfmtParser, SyslogParser};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn test_logfmt_parser() {
+        let fields = LogfmtParser
+            .parse(r#"level=info msg="request \"done\"" status=200 cached"#)
+            .unwrap();
+        assert_eq!(fields["level"].as_str(), Some("info"));
+        assert_eq!(fields["msg"].as_str(), Some("request \"done\""));
+        assert_eq!(fields["status"].as_i64(), Some(200));
+        assert_eq!(fields["cached"].as_str(), Some("true"));
+
+        assert!(LogfmtParser.parse(r#"msg="unclosed"#).is_err());
+        assert!(LogfmtParser.parse("=value").is_err());
+    }
+
+    #[test]
+    fn test_syslog_parser() {
+        let parser = SyslogParser::new();
+
+        let fields = parser
+            .parse("<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 - 'su root' failed")
+            .unwrap();
+        assert_eq!(fields["facility"].as_i64(), Some(4));
+        assert_eq!(fields["severity"].as_i64(), Some(2));
+        assert_eq!(fields["version"].as_i64(), Some(1));
+        assert_eq!(
+            fields["timestamp"].as_timestamp(),
+            Some(UNIX_EPOCH + Duration::from_millis(1065910455003))
+        );
+        assert_eq!(fields["hostname"].as_str(), Some("mymachine"));
+        assert_eq!(fields["app_name"].as_str(), Some("su"));
+        assert!(!fields.contains_key("proc_id"));
+        assert_eq!(fields["message"].as_str(), Some("'su root' failed"));
+
+        let fields = parser
+            .parse("Oct 11 22:14:15 mymachine sshd[1234]: Accepted publickey")
+            .unwrap();
+        assert!(!fields.contains_key("facility"));
+        assert_eq!(fields["timestamp"].as_str(), Some("Oct 11 22:14:15"));
+        assert_eq!(fields["app_name"].as_str(), Some("sshd"));
+        assert_eq!(fields["proc_id"].as_i64(), Some(1234));
+        assert_eq!(fields["message"].as_str(), Some("Accepted publickey"));
+
+        assert!(parser.parse("not syslog").is_err());
+        assert!(parser
+            .parse("<999>Oct 11 22:14:15 mymachine sshd: Accepted publickey")
+            .is_err());
+    }
+
+    #[test]
+    fn test_common_log_parser() {
+        let parser = CommonLogParser::new();
+
+        let fields = parser
+            .parse(r#"127.0.0.1 - frank [10/Oct/2000:13:55:36 -0700] "GET /apache_pb.gif HTTP/1.0" 200 2326 "http://www.example.com/start.html" "Mozilla/4.08""#)
+            .unwrap();
+        assert_eq!(fields["remote_host"].as_str(), Some("127.0.0.1"));
+        assert!(!fields.contains_key("ident"));
+        assert_eq!(fields["user"].as_str(), Some("frank"));
+        assert_eq!(
+            fields["timestamp"],
+            FieldValue::Timestamp(UNIX_EPOCH + Duration::from_secs(971211336))
+        );
+        assert_eq!(fields["method"].as_str(), Some("GET"));
+        assert_eq!(fields["path"].as_str(), Some("/apache_pb.gif"));
+        assert_eq!(fields["status"].as_i64(), Some(200));
+        assert_eq!(fields["bytes"].as_i64(), Some(2326));
+        assert_eq!(fields["user_agent"].as_str(), Some("Mozilla/4.08"));
+
+        // common log format without referer and user agent
+        let fields = parser
+            .parse(r#"10.0.0.1 - - [01/Jan/2024:00:00:00 +0000] "POST /api HTTP/1.1" 500 -"#)
+            .unwrap();
+        assert_eq!(fields["status"].as_i64(), Some(500));
+        assert!(!fields.contains_key("bytes"));
+        assert!(!fields.contains_key("referer"));
+
+        // the timestamps out of range are left as strings
+        for timestamp in [
+            "10/Oct/2000:99999999999999999:00:00 +0000",
+            "10/Oct/2000:24:00:00 +0000",
+            "99/Oct/2000:00:00:00 +0000",
+            "10/Oct/99999999999999999:00:00:00 +0000",
+            "10/Oct/1969:00:00:00 +0000",
+        ] {
+            let line = format!(r#"10.0.0.1 - - [{}] "GET / HTTP/1.1" 200 -"#, timestamp);
+            let fields = parser.parse(&line).unwrap();
+            assert_eq!(fields["timestamp"].as_str(), Some(timestamp));
+        }
+    }
+}
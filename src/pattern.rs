@@ -0,0 +1,104 @@
+use crate::Error;
+
+use regex::Regex;
+use std::path::{Path, PathBuf};
+
+// Files to monitor within a directory, given by a glob pattern or a regular expression.
+#[derive(Debug, Clone)]
+pub struct FilePattern {
+    dir: PathBuf,
+    matcher: Matcher,
+}
+
+// matched against the file name
+#[derive(Debug, Clone)]
+enum Matcher {
+    Glob(glob::Pattern),
+    Regex(Regex),
+}
+
+impl FilePattern {
+    // glob pattern of the file name, e.g. "/var/log/app/*.log".
+    // the directory part of the pattern must not contain any wildcard.
+    pub fn glob(pattern: &str) -> Result<Self, Error> {
+        let path = Path::new(pattern);
+        let dir = path.parent().unwrap_or(Path::new(""));
+        let file_name = path.file_name().unwrap_or_default();
+        let dir_str = dir.to_string_lossy();
+        if glob::Pattern::escape(&dir_str) != dir_str {
+            return Err(Error::InvalidPattern(format!(
+                "{} - wildcards are only allowed in the file name",
+                pattern
+            )));
+        }
+        let glob = glob::Pattern::new(&file_name.to_string_lossy())
+            .map_err(|e| Error::InvalidPattern(format!("{} - {}", pattern, e)))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            matcher: Matcher::Glob(glob),
+        })
+    }
+
+    // regular expression of the file name in the directory.
+    pub fn regex<P: AsRef<Path>>(dir: P, file_name: &str) -> Result<Self, Error> {
+        let regex = Regex::new(file_name).map_err(|e| Error::InvalidPattern(e.to_string()))?;
+        Ok(Self {
+            dir: dir.as_ref().to_path_buf(),
+            matcher: Matcher::Regex(regex),
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn matches<P: AsRef<Path>>(&self, path: P) -> bool {
+        let path = path.as_ref();
+        if path.parent() != Some(self.dir.as_path()) {
+            return false;
+        }
+
+        let file_name = match path.file_name() {
+            Some(file_name) => file_name.to_string_lossy(),
+            None => return false,
+        };
+        match &self.matcher {
+            Matcher::Glob(pattern) => pattern.matches(&file_name),
+            Matcher::Regex(regex) => regex.is_match(&file_name),
+        }
+    }
+
+    // the same pattern in another directory, used to make the directory absolute.
+    pub(crate) fn with_dir(self, dir: PathBuf) -> Self {
+        Self { dir, ..self }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FilePattern;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn test_file_pattern() {
+        let pattern = FilePattern::glob("/var/log/app/*.log").unwrap();
+        assert_eq!(pattern.dir(), Path::new("/var/log/app"));
+        assert!(pattern.matches("/var/log/app/server.log"));
+        assert!(!pattern.matches("/var/log/app/server.log.1"));
+        assert!(!pattern.matches("/var/log/server.log"));
+
+        let pattern = FilePattern::regex("/var/log/app", r"^worker-\d+\.log$").unwrap();
+        assert!(pattern.matches("/var/log/app/worker-1.log"));
+        assert!(!pattern.matches("/var/log/app/worker-a.log"));
+        assert!(!pattern.matches("/var/log/other/worker-1.log"));
+
+        let pattern = FilePattern::glob("logs/*.log")
+            .unwrap()
+            .with_dir(PathBuf::from("/home/user/logs"));
+        assert!(pattern.matches("/home/user/logs/a.log"));
+
+        assert!(FilePattern::glob("/var/log/[.log").is_err());
+        assert!(FilePattern::glob("/var/log/*/app.log").is_err());
+        assert!(FilePattern::regex("/var/log", "(").is_err());
+    }
+}
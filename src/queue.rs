@@ -0,0 +1,245 @@
+use crate::{rt, Overflow};
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+// Bounded queue of the events of a registration, between the worker reading the file and the
+// task running the callbacks.
+//
+// when the queue is full, a new line is handled by the overflow policy. the other events (e.g.
+// a rotation or an error) are always queued. the number of dropped lines is returned with the
+// next event taken by the task.
+
+// an event which can be dropped by the overflow policy.
+pub(crate) trait Item {
+    // whether the overflow policy may drop the item, e.g. a line.
+    fn is_droppable(&self) -> bool;
+    // whether the item repeats `other`, for `Overflow::Coalesce`.
+    fn repeats(&self, other: &Self) -> bool;
+}
+
+struct Items<T> {
+    queue: VecDeque<T>,
+    // the items dropped since the last item was taken.
+    dropped: u64,
+    // the items taken whose callbacks haven't returned yet.
+    running: usize,
+}
+
+pub(crate) struct QueueSender<T> {
+    items: Arc<Mutex<Items<T>>>,
+    capacity: usize,
+    overflow: Overflow,
+    // wakes the task up when an item is queued.
+    ready: rt::Sender<()>,
+    // woken up when an item is taken or done, to wait for room or for the callbacks.
+    progress: rt::Mutex<rt::Receiver<()>>,
+}
+
+pub(crate) struct QueueReceiver<T> {
+    items: Arc<Mutex<Items<T>>>,
+    ready: rt::Receiver<()>,
+    progress: rt::Sender<()>,
+}
+
+// An item taken from the queue, done once dropped.
+pub(crate) struct Running<T> {
+    items: Arc<Mutex<Items<T>>>,
+    progress: rt::Sender<()>,
+}
+
+pub(crate) fn queue<T>(capacity: usize, overflow: Overflow) -> (QueueSender<T>, QueueReceiver<T>) {
+    let items = Arc::new(Mutex::new(Items {
+        queue: VecDeque::new(),
+        dropped: 0,
+        running: 0,
+    }));
+    let (ready_sender, ready_receiver) = rt::bounded(1);
+    let (progress_sender, progress_receiver) = rt::bounded(1);
+    let sender = QueueSender {
+        items: Arc::clone(&items),
+        capacity: capacity.max(1),
+        overflow,
+        ready: ready_sender,
+        progress: rt::Mutex::new(progress_receiver),
+    };
+    let receiver = QueueReceiver {
+        items,
+        ready: ready_receiver,
+        progress: progress_sender,
+    };
+    (sender, receiver)
+}
+
+impl<T: Item> QueueSender<T> {
+    // queue an item. with `Overflow::Block`, or a line which doesn't repeat the last one for
+    // `Overflow::Coalesce`, waits until the task takes an item when the queue is full.
+    pub(crate) async fn send(&self, item: T) {
+        loop {
+            {
+                let mut items = self.items.lock().unwrap();
+                if !item.is_droppable() || items.queue.len() < self.capacity {
+                    items.queue.push_back(item);
+                    break;
+                }
+                match self.overflow {
+                    Overflow::Block => {}
+                    Overflow::DropOldest => {
+                        // the new line is dropped if only other events are queued
+                        items.dropped += 1;
+                        match items.queue.iter().position(T::is_droppable) {
+                            Some(oldest) => {
+                                items.queue.remove(oldest);
+                                items.queue.push_back(item);
+                                break;
+                            }
+                            None => return,
+                        }
+                    }
+                    Overflow::DropNewest => {
+                        items.dropped += 1;
+                        return;
+                    }
+                    Overflow::Coalesce => {
+                        if items.queue.back().is_some_and(|last| item.repeats(last)) {
+                            items.dropped += 1;
+                            return;
+                        }
+                    }
+                }
+            }
+            // the task has stopped
+            if self.progress.lock().await.recv().await.is_none() {
+                return;
+            }
+        }
+        // the task is already woken up if the channel is full
+        let _ = self.ready.try_send(());
+    }
+}
+
+impl<T> QueueSender<T> {
+    // wait until the queued items are taken and done.
+    pub(crate) async fn drained(&self) {
+        loop {
+            {
+                let items = self.items.lock().unwrap();
+                if items.queue.is_empty() && items.running == 0 {
+                    return;
+                }
+            }
+            if self.progress.lock().await.recv().await.is_none() {
+                return;
+            }
+        }
+    }
+}
+
+impl<T> QueueReceiver<T> {
+    // the next item and the number of items dropped before it. the item is done once the
+    // `Running` is dropped. returns `None` once the sender is dropped and the queue is empty.
+    pub(crate) async fn recv(&mut self) -> Option<(T, u64, Running<T>)> {
+        loop {
+            {
+                let mut items = self.items.lock().unwrap();
+                if let Some(item) = items.queue.pop_front() {
+                    let dropped = std::mem::take(&mut items.dropped);
+                    items.running += 1;
+                    drop(items);
+                    let _ = self.progress.try_send(());
+                    let running = Running {
+                        items: Arc::clone(&self.items),
+                        progress: self.progress.clone(),
+                    };
+                    return Some((item, dropped, running));
+                }
+            }
+            self.ready.recv().await?;
+        }
+    }
+}
+
+impl<T> Drop for Running<T> {
+    fn drop(&mut self) {
+        self.items.lock().unwrap().running -= 1;
+        let _ = self.progress.try_send(());
+    }
+}
+
+#[cfg(all(test, feature = "async-std"))]
+mod tests {
+    use super::{queue, Item};
+    use crate::Overflow;
+
+    // the item 0 isn't droppable.
+    #[derive(Debug, PartialEq)]
+    struct Line(u32);
+
+    impl Item for Line {
+        fn is_droppable(&self) -> bool {
+            self.0 != 0
+        }
+
+        fn repeats(&self, other: &Self) -> bool {
+            self.0 == other.0
+        }
+    }
+
+    async fn send_all(overflow: Overflow, lines: &[u32]) -> Vec<(Line, u64)> {
+        let (sender, mut receiver) = queue(2, overflow);
+        for line in lines {
+            sender.send(Line(*line)).await;
+        }
+        drop(sender);
+        let mut received = Vec::new();
+        while let Some((line, dropped, _)) = receiver.recv().await {
+            received.push((line, dropped));
+        }
+        received
+    }
+
+    #[async_std::test]
+    async fn test_queue_overflow() {
+        assert_eq!(
+            send_all(Overflow::DropOldest, &[1, 2, 3, 4]).await,
+            vec![(Line(3), 2), (Line(4), 0)]
+        );
+        assert_eq!(
+            send_all(Overflow::DropNewest, &[1, 2, 3, 4]).await,
+            vec![(Line(1), 2), (Line(2), 0)]
+        );
+        // the repeats are only dropped when the queue is full
+        assert_eq!(
+            send_all(Overflow::Coalesce, &[1, 1, 1, 1]).await,
+            vec![(Line(1), 2), (Line(1), 0)]
+        );
+        // the other events are queued anyway
+        assert_eq!(
+            send_all(Overflow::DropNewest, &[1, 2, 0, 3]).await,
+            vec![(Line(1), 1), (Line(2), 0), (Line(0), 0)]
+        );
+        assert_eq!(
+            send_all(Overflow::DropOldest, &[0, 0, 1]).await,
+            vec![(Line(0), 1), (Line(0), 0)]
+        );
+    }
+
+    #[async_std::test]
+    async fn test_queue_block() {
+        let (sender, mut receiver) = queue(1, Overflow::Block);
+        let task = async_std::task::spawn(async move {
+            for line in 1..=5 {
+                sender.send(Line(line)).await;
+            }
+            // the last line is done once its `Running` is dropped
+            sender.drained().await;
+        });
+        let mut received = Vec::new();
+        while let Some((line, dropped, _)) = receiver.recv().await {
+            assert_eq!(dropped, 0);
+            received.push(line);
+        }
+        task.await;
+        assert_eq!(received, (1..=5).map(Line).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,381 @@
+// Abstraction over the async runtime. the runtime is selected by the `async-std` (default) or
+// the `tokio` feature.
+
+#[cfg(all(feature = "async-std", feature = "tokio"))]
+compile_error!(
+    "features `async-std` and `tokio` are mutually exclusive, disable the default features to use `tokio`"
+);
+
+#[cfg(not(any(feature = "async-std", feature = "tokio")))]
+compile_error!("either feature `async-std` or `tokio` must be enabled");
+
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+//==== async-std
+
+#[cfg(feature = "async-std")]
+pub(crate) use async_std::{fs, sync::Mutex};
+
+#[cfg(feature = "async-std")]
+pub(crate) mod io {
+    pub(crate) use async_std::io::{prelude::*, BufReader, Cursor};
+
+    // a buffered reader of a file or of the decompressed content of an archive.
+    pub(crate) trait Reader:
+        async_std::io::BufRead + async_std::io::Seek + Unpin + Send
+    {
+    }
+
+    impl<R: async_std::io::BufRead + async_std::io::Seek + Unpin + Send> Reader for R {}
+}
+
+#[cfg(feature = "async-std")]
+pub(crate) struct JoinHandle<T>(async_std::task::JoinHandle<T>);
+
+#[cfg(feature = "async-std")]
+pub(crate) fn spawn<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    JoinHandle(async_std::task::spawn(future))
+}
+
+#[cfg(feature = "async-std")]
+impl<T> Future for JoinHandle<T> {
+    type Output = T;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        Pin::new(&mut self.0).poll(cx)
+    }
+}
+
+// Handle of the runtime, to spawn a task where the runtime may not be current, e.g. in a drop.
+#[cfg(feature = "async-std")]
+pub(crate) struct Handle;
+
+#[cfg(feature = "async-std")]
+impl Handle {
+    pub(crate) fn try_current() -> Option<Self> {
+        Some(Handle)
+    }
+
+    pub(crate) fn spawn<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        async_std::task::spawn(future);
+    }
+}
+
+#[cfg(feature = "async-std")]
+pub(crate) async fn spawn_blocking<F, T>(f: F) -> T
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    async_std::task::spawn_blocking(f).await
+}
+
+#[cfg(feature = "async-std")]
+pub(crate) async fn sleep(duration: std::time::Duration) {
+    async_std::task::sleep(duration).await
+}
+
+#[cfg(feature = "async-std")]
+pub(crate) struct Sender<T>(async_std::channel::Sender<T>);
+
+#[cfg(feature = "async-std")]
+pub(crate) struct Receiver<T>(async_std::channel::Receiver<T>);
+
+#[cfg(feature = "async-std")]
+pub(crate) struct UnboundedSender<T>(async_std::channel::Sender<T>);
+
+#[cfg(feature = "async-std")]
+pub(crate) struct UnboundedReceiver<T>(async_std::channel::Receiver<T>);
+
+#[cfg(feature = "async-std")]
+pub(crate) fn bounded<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    let (sender, receiver) = async_std::channel::bounded(capacity);
+    (Sender(sender), Receiver(receiver))
+}
+
+#[cfg(feature = "async-std")]
+pub(crate) fn unbounded<T>() -> (UnboundedSender<T>, UnboundedReceiver<T>) {
+    let (sender, receiver) = async_std::channel::unbounded();
+    (UnboundedSender(sender), UnboundedReceiver(receiver))
+}
+
+#[cfg(feature = "async-std")]
+impl<T> Sender<T> {
+    // returns an error when the receiver has been dropped.
+    pub(crate) async fn send(&self, value: T) -> Result<(), T> {
+        self.0.send(value).await.map_err(|e| e.into_inner())
+    }
+
+    // returns an error when the channel is full or the receiver has been dropped.
+    pub(crate) fn try_send(&self, value: T) -> Result<(), T> {
+        self.0.try_send(value).map_err(|e| e.into_inner())
+    }
+}
+
+#[cfg(feature = "async-std")]
+impl<T> Receiver<T> {
+    pub(crate) fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        use futures_core::Stream;
+        Pin::new(&mut self.0).poll_next(cx)
+    }
+}
+
+#[cfg(feature = "async-std")]
+impl<T> UnboundedSender<T> {
+    // returns an error when the receiver has been dropped.
+    pub(crate) fn send(&self, value: T) -> Result<(), T> {
+        self.0.try_send(value).map_err(|e| e.into_inner())
+    }
+}
+
+#[cfg(feature = "async-std")]
+impl<T> UnboundedReceiver<T> {
+    pub(crate) async fn recv(&mut self) -> Option<T> {
+        self.0.recv().await.ok()
+    }
+}
+
+// limits the number of tasks running at once. the permits are the values of a channel.
+#[cfg(feature = "async-std")]
+#[derive(Clone)]
+pub(crate) struct Semaphore {
+    sender: async_std::channel::Sender<()>,
+    receiver: async_std::channel::Receiver<()>,
+}
+
+#[cfg(feature = "async-std")]
+pub(crate) struct Permit(async_std::channel::Sender<()>);
+
+#[cfg(feature = "async-std")]
+impl Semaphore {
+    pub(crate) fn new(permits: usize) -> Self {
+        let (sender, receiver) = async_std::channel::bounded(permits);
+        for _ in 0..permits {
+            let _ = sender.try_send(());
+        }
+        Self { sender, receiver }
+    }
+
+    pub(crate) async fn acquire(&self) -> Permit {
+        // both ends are kept, so the channel can't be closed
+        let _ = self.receiver.recv().await;
+        Permit(self.sender.clone())
+    }
+}
+
+#[cfg(feature = "async-std")]
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let _ = self.0.try_send(());
+    }
+}
+
+//==== tokio
+
+#[cfg(feature = "tokio")]
+pub(crate) use tokio::{fs, sync::Mutex};
+
+#[cfg(feature = "tokio")]
+pub(crate) mod io {
+    pub(crate) use std::io::Cursor;
+    pub(crate) use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncSeekExt, BufReader};
+
+    // a buffered reader of a file or of the decompressed content of an archive.
+    pub(crate) trait Reader:
+        tokio::io::AsyncBufRead + tokio::io::AsyncSeek + Unpin + Send
+    {
+    }
+
+    impl<R: tokio::io::AsyncBufRead + tokio::io::AsyncSeek + Unpin + Send> Reader for R {}
+}
+
+#[cfg(feature = "tokio")]
+pub(crate) struct JoinHandle<T>(tokio::task::JoinHandle<T>);
+
+#[cfg(feature = "tokio")]
+pub(crate) fn spawn<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    JoinHandle(tokio::spawn(future))
+}
+
+#[cfg(feature = "tokio")]
+impl<T> Future for JoinHandle<T> {
+    type Output = T;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        match Pin::new(&mut self.0).poll(cx) {
+            Poll::Ready(Ok(value)) => Poll::Ready(value),
+            // the task is never cancelled, it can only have panicked
+            Poll::Ready(Err(e)) => std::panic::resume_unwind(e.into_panic()),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+pub(crate) struct Handle(tokio::runtime::Handle);
+
+#[cfg(feature = "tokio")]
+impl Handle {
+    // `None` outside of a tokio runtime.
+    pub(crate) fn try_current() -> Option<Self> {
+        tokio::runtime::Handle::try_current().ok().map(Handle)
+    }
+
+    pub(crate) fn spawn<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.0.spawn(future);
+    }
+}
+
+#[cfg(feature = "tokio")]
+pub(crate) async fn spawn_blocking<F, T>(f: F) -> T
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    match tokio::task::spawn_blocking(f).await {
+        Ok(value) => value,
+        Err(e) => std::panic::resume_unwind(e.into_panic()),
+    }
+}
+
+#[cfg(feature = "tokio")]
+pub(crate) async fn sleep(duration: std::time::Duration) {
+    tokio::time::sleep(duration).await
+}
+
+#[cfg(feature = "tokio")]
+pub(crate) struct Sender<T>(tokio::sync::mpsc::Sender<T>);
+
+#[cfg(feature = "tokio")]
+pub(crate) struct Receiver<T>(tokio::sync::mpsc::Receiver<T>);
+
+#[cfg(feature = "tokio")]
+pub(crate) struct UnboundedSender<T>(tokio::sync::mpsc::UnboundedSender<T>);
+
+#[cfg(feature = "tokio")]
+pub(crate) struct UnboundedReceiver<T>(tokio::sync::mpsc::UnboundedReceiver<T>);
+
+#[cfg(feature = "tokio")]
+pub(crate) fn bounded<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    let (sender, receiver) = tokio::sync::mpsc::channel(capacity);
+    (Sender(sender), Receiver(receiver))
+}
+
+#[cfg(feature = "tokio")]
+pub(crate) fn unbounded<T>() -> (UnboundedSender<T>, UnboundedReceiver<T>) {
+    let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+    (UnboundedSender(sender), UnboundedReceiver(receiver))
+}
+
+#[cfg(feature = "tokio")]
+impl<T> Sender<T> {
+    // returns an error when the receiver has been dropped.
+    pub(crate) async fn send(&self, value: T) -> Result<(), T> {
+        self.0.send(value).await.map_err(|e| e.0)
+    }
+
+    // returns an error when the channel is full or the receiver has been dropped.
+    pub(crate) fn try_send(&self, value: T) -> Result<(), T> {
+        self.0.try_send(value).map_err(|e| match e {
+            tokio::sync::mpsc::error::TrySendError::Full(value) => value,
+            tokio::sync::mpsc::error::TrySendError::Closed(value) => value,
+        })
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<T> Receiver<T> {
+    pub(crate) fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.0.poll_recv(cx)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<T> UnboundedSender<T> {
+    // returns an error when the receiver has been dropped.
+    pub(crate) fn send(&self, value: T) -> Result<(), T> {
+        self.0.send(value).map_err(|e| e.0)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<T> UnboundedReceiver<T> {
+    pub(crate) async fn recv(&mut self) -> Option<T> {
+        self.0.recv().await
+    }
+}
+
+// limits the number of tasks running at once.
+#[cfg(feature = "tokio")]
+#[derive(Clone)]
+pub(crate) struct Semaphore(std::sync::Arc<tokio::sync::Semaphore>);
+
+#[cfg(feature = "tokio")]
+pub(crate) struct Permit {
+    _permit: tokio::sync::OwnedSemaphorePermit,
+}
+
+#[cfg(feature = "tokio")]
+impl Semaphore {
+    pub(crate) fn new(permits: usize) -> Self {
+        Self(std::sync::Arc::new(tokio::sync::Semaphore::new(permits)))
+    }
+
+    pub(crate) async fn acquire(&self) -> Permit {
+        // the semaphore is never closed
+        let permit = self.0.clone().acquire_owned().await.unwrap();
+        Permit { _permit: permit }
+    }
+}
+
+//==== common
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> Receiver<T> {
+    pub(crate) async fn recv(&mut self) -> Option<T> {
+        std::future::poll_fn(|cx| self.poll_recv(cx)).await
+    }
+}
+
+impl<T> Clone for UnboundedSender<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+// the paths of the files in a directory.
+pub(crate) async fn read_dir_files(dir: PathBuf) -> std::io::Result<Vec<PathBuf>> {
+    spawn_blocking(move || {
+        let mut files = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_file() {
+                files.push(entry.path());
+            }
+        }
+        Ok(files)
+    })
+    .await
+}
@@ -0,0 +1,85 @@
+use crate::{rt, rt::Receiver, LogEvent, LogWatcherHandle, SubscriptionId};
+
+use futures_core::Stream;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+// default number of events buffered by a stream.
+pub(crate) const DEFAULT_STREAM_CAPACITY: usize = 1024;
+
+// Stream of the events of a registered file.
+//
+// The events are buffered up to the capacity of the stream. when the buffer is full, the
+// reading of the file waits until the events are consumed. the registration is unsubscribed
+// when the stream is dropped.
+pub struct LogStream {
+    id: SubscriptionId,
+    receiver: Receiver<LogEvent>,
+    handle: LogWatcherHandle,
+    // the runtime the stream has been created in, to unsubscribe when it is dropped.
+    runtime: Option<rt::Handle>,
+}
+
+impl LogStream {
+    pub(crate) fn new(
+        id: SubscriptionId,
+        receiver: Receiver<LogEvent>,
+        handle: LogWatcherHandle,
+    ) -> Self {
+        Self {
+            id,
+            receiver,
+            handle,
+            runtime: rt::Handle::try_current(),
+        }
+    }
+
+    // the registration of the stream, to unsubscribe it.
+    pub fn subscription_id(&self) -> SubscriptionId {
+        self.id
+    }
+}
+
+impl Stream for LogStream {
+    type Item = LogEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+impl Drop for LogStream {
+    fn drop(&mut self) {
+        // the locks of the watcher can't be awaited here. without a runtime, e.g. once it has
+        // been shut down, the registration stays until the watcher is dropped.
+        let Some(runtime) = &self.runtime else {
+            return;
+        };
+        let (handle, id) = (self.handle.clone(), self.id);
+        runtime.spawn(async move {
+            let _ = handle.unsubscribe(id).await;
+        });
+    }
+}
+
+#[cfg(all(test, feature = "async-std"))]
+mod tests {
+    use crate::LogWatcherHandle;
+    use std::time::Duration;
+
+    #[async_std::test]
+    async fn test_stream_drop() {
+        let handle = LogWatcherHandle::new(Default::default());
+        let stream = handle
+            .register_stream("test-stream-drop.txt", None)
+            .await
+            .unwrap();
+        assert_eq!(handle.log_callbacks.lock().await.len(), 1);
+
+        // the registration is removed with the stream
+        drop(stream);
+        async_std::task::sleep(Duration::from_millis(100)).await;
+        assert!(handle.log_callbacks.lock().await.is_empty());
+        assert!(handle.tail_states.lock().await.is_empty());
+    }
+}
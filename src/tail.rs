@@ -0,0 +1,878 @@
+use crate::archive;
+use crate::monitor::EventSender;
+use crate::multiline::Record;
+use crate::offset::{Checkpoint, CheckpointKey, Fingerprint};
+use crate::{
+    Encoding, ErrorKind, Framing, LogCallback, LogError, LogEvent, Registration, Rotation,
+    StartPosition,
+};
+
+use crate::rt::{
+    self,
+    fs::{self, File},
+    io::*,
+};
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::io::SeekFrom;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+// identity of a file on disk, used to tell a rotated file from a new one at the same path.
+#[cfg(unix)]
+fn file_id(metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+// Reading state of a watched file, kept between the events.
+#[derive(Default)]
+pub(crate) struct TailState {
+    // the file stays open so that a rotated-away file can still be drained.
+    // a file which is not open yet is read from the beginning once it is created.
+    reader: Option<BufReader<File>>,
+    file_id: Option<(u64, u64)>,
+    position: u64,
+    // the offset saved in the offset store of the registration.
+    committed: Option<u64>,
+    // the record being assembled from several lines.
+    record: Record,
+    // the length of the partial line at the end of the file, and when it last changed.
+    partial: Option<(u64, Instant)>,
+    // the beginning of the file read so far, to find its copy after a copytruncate.
+    head: Option<Fingerprint>,
+    // the saved offset in the rotated copies of the file, read on the first update so that
+    // the lines are queued like the others.
+    catch_up: Option<Checkpoint>,
+    // a record can't be framed, e.g. its length is over the maximum. there is no boundary to
+    // go on from, the file is read again once it is replaced or truncated.
+    frame_error: bool,
+    // when the pending flush timer of the incomplete record or the partial line fires.
+    flush_timer: Option<Instant>,
+    // the events read by the worker of the file, waiting for their callbacks.
+    events: Option<Arc<EventSender>>,
+}
+
+impl TailState {
+    // open the file at the saved offset, or at the start position of the registration.
+    // a missing file is read from the beginning once it is created.
+    pub(crate) async fn open(path: &Path, registration: &Registration) -> Self {
+        let Ok(file) = File::open(path).await else {
+            return Self::default();
+        };
+        let file_id = file.metadata().await.ok().as_ref().and_then(file_id);
+        let mut reader = BufReader::new(file);
+
+        let mut catch_up = None;
+        let position = match load_checkpoint(path, registration).await {
+            Some(checkpoint) => {
+                let len = reader.get_ref().metadata().await.map_or(0, |m| m.len());
+                if checkpoint.offset <= len
+                    && checkpoint.fingerprint.matches(&mut reader, file_id).await
+                {
+                    checkpoint.offset
+                } else {
+                    // the file has been replaced since the offset was saved, the lines missed
+                    // are read from its rotated copy first
+                    catch_up = Some(checkpoint);
+                    0
+                }
+            }
+            None => find_start_position(&mut reader, registration).await,
+        };
+        Self {
+            reader: Some(reader),
+            file_id,
+            position,
+            committed: None,
+            record: Record::default(),
+            partial: None,
+            head: None,
+            catch_up,
+            frame_error: false,
+            flush_timer: None,
+            events: None,
+        }
+    }
+
+    // whether the rotated copies of the file are still to be read by the worker.
+    pub(crate) fn has_catch_up(&self) -> bool {
+        self.catch_up.is_some()
+    }
+
+    // the queue of the events of the registration, created the first time the file is read by
+    // its worker.
+    pub(crate) fn events_or_insert_with(
+        &mut self,
+        f: impl FnOnce() -> EventSender,
+    ) -> Arc<EventSender> {
+        Arc::clone(self.events.get_or_insert_with(|| Arc::new(f())))
+    }
+
+    // the task running the callbacks stops once the queued events are delivered.
+    pub(crate) fn close_events(&mut self) {
+        self.events = None;
+    }
+
+    pub(crate) fn is_open(&self) -> bool {
+        self.reader.is_some()
+    }
+
+    // the timeout of the flush timer to start for the incomplete record or the partial line,
+    // if no timer is pending. a timer firing before they expire is started again by the flush.
+    pub(crate) fn arm_flush_timer(&mut self, registration: &Registration) -> Option<Duration> {
+        let now = Instant::now();
+        if self.flush_timer.is_some_and(|timer| timer > now) {
+            return None;
+        }
+        let record = (registration.multiline.as_ref())
+            .and_then(|multiline| self.record.expires_in(multiline.flush_timeout()));
+        let partial = (registration.partial_flush.zip(self.partial))
+            .map(|(timeout, (_, since))| timeout.saturating_sub(since.elapsed()));
+        let timeout = record.into_iter().chain(partial).min()?;
+        self.flush_timer = Some(now + timeout);
+        Some(timeout)
+    }
+
+    // check the file for rotation and deliver the newly appended lines.
+    pub(crate) async fn update(&mut self, path: &Path, registration: &Registration) {
+        let callback = &registration.callback;
+        if let Some(checkpoint) = self.catch_up.take() {
+            let (head, offset) = (&checkpoint.fingerprint, checkpoint.offset);
+            catch_up(path, head, offset, &mut self.record, registration).await;
+        }
+        let metadata = fs::metadata(path).await.ok();
+
+        if let Some(reader) = &mut self.reader {
+            let rotated = match &metadata {
+                Some(metadata) => file_id(metadata) != self.file_id,
+                None => true,
+            };
+
+            if rotated {
+                // the file has been renamed or removed, finish reading the old one first
+                if registration.drain_rotated && !self.frame_error {
+                    read_lines(
+                        reader,
+                        &mut self.position,
+                        &mut self.record,
+                        path,
+                        None,
+                        registration,
+                    )
+                    .await;
+                }
+                self.reader = None;
+                self.flush_record(path, registration, Duration::ZERO).await;
+                callback(LogEvent::new_rotation(path.to_owned(), Rotation::Removed)).await;
+            } else if metadata.as_ref().map_or(0, |m| m.len()) < self.position {
+                // the file has been truncated in place (e.g. copytruncate), the rest is read
+                // from the copy
+                if let (true, Some(head)) = (registration.drain_rotated, self.head) {
+                    catch_up(path, &head, self.position, &mut self.record, registration).await;
+                }
+                self.flush_record(path, registration, Duration::ZERO).await;
+                self.position = 0;
+                self.committed = None;
+                self.partial = None;
+                self.head = None;
+                self.frame_error = false;
+                callback(LogEvent::new_rotation(path.to_owned(), Rotation::Truncated)).await;
+            }
+        }
+
+        if self.reader.is_none() {
+            // wait for the file to be created
+            if metadata.is_none() {
+                return;
+            }
+
+            let file = match File::open(path).await {
+                Ok(file) => file,
+                Err(e) => {
+                    let log_error = LogError {
+                        kind: ErrorKind::FileOpenError(e),
+                    };
+                    callback(LogEvent::new(path.to_owned(), None, Some(log_error))).await;
+                    return;
+                }
+            };
+            self.file_id = file.metadata().await.ok().as_ref().and_then(file_id);
+            self.reader = Some(BufReader::new(file));
+            self.position = 0;
+            self.committed = None;
+            self.partial = None;
+            self.head = None;
+            self.frame_error = false;
+            callback(LogEvent::new_rotation(path.to_owned(), Rotation::Created)).await;
+        }
+
+        if let (Some(reader), false) = (&mut self.reader, self.frame_error) {
+            self.frame_error = !read_lines(
+                reader,
+                &mut self.position,
+                &mut self.record,
+                path,
+                None,
+                registration,
+            )
+            .await;
+        }
+        if registration.partial_flush.is_some() {
+            self.track_partial().await;
+        }
+        if registration.drain_rotated && !self.head.is_some_and(|head| head.is_full()) {
+            self.read_head().await;
+        }
+        self.commit(path, registration).await;
+    }
+
+    // deliver the incomplete record and the partial line whose timeouts have elapsed.
+    pub(crate) async fn flush(&mut self, path: &Path, registration: &Registration) {
+        if let Some(multiline) = &registration.multiline {
+            self.flush_record(path, registration, multiline.flush_timeout())
+                .await;
+        }
+        if let Some(timeout) = registration.partial_flush {
+            self.flush_partial(path, registration, timeout).await;
+        }
+        if let Some(batch) = &registration.batch {
+            self.flush_batch(path, registration, batch.max_wait()).await;
+        }
+    }
+
+    // deliver the incomplete record once no line has been added for the timeout.
+    pub(crate) async fn flush_record(
+        &mut self,
+        path: &Path,
+        registration: &Registration,
+        timeout: Duration,
+    ) {
+        if let Some(record) = self.record.take_expired(timeout) {
+            deliver(path, None, record, registration, false).await;
+            self.commit(path, registration).await;
+        }
+    }
+
+    // deliver the batch once its first event has waited for the timeout, then save the offset.
+    pub(crate) async fn flush_batch(
+        &mut self,
+        path: &Path,
+        registration: &Registration,
+        timeout: Duration,
+    ) {
+        let Some(batch) = &registration.batch else {
+            return;
+        };
+        if batch.flush(timeout).await {
+            self.commit(path, registration).await;
+        }
+    }
+
+    async fn read_head(&mut self) {
+        let Some(reader) = &mut self.reader else {
+            return;
+        };
+        // the head read so far is kept if the file has just been truncated
+        if self.position > 0 {
+            if let Ok(head) = Fingerprint::read(reader, None, self.position).await {
+                self.head = Some(head);
+            }
+        }
+    }
+
+    // remember when the bytes after the last complete line have changed.
+    async fn track_partial(&mut self) {
+        let Some(reader) = &self.reader else {
+            return;
+        };
+        let len = reader.get_ref().metadata().await.map_or(0, |m| m.len());
+        let partial_len = len.saturating_sub(self.position);
+        self.partial = match self.partial {
+            _ if partial_len == 0 => None,
+            Some((len, since)) if len == partial_len => Some((len, since)),
+            _ => Some((partial_len, Instant::now())),
+        };
+    }
+
+    // deliver the partial line once it hasn't grown for the timeout. the position moves past
+    // it, so that the rest of the line is delivered as a new line.
+    async fn flush_partial(&mut self, path: &Path, registration: &Registration, timeout: Duration) {
+        let (Some((_, since)), Some(reader)) = (self.partial, &mut self.reader) else {
+            return;
+        };
+        if since.elapsed() < timeout {
+            return;
+        }
+        self.partial = None;
+
+        let mut bytes = Vec::new();
+        if reader.seek(SeekFrom::Start(self.position)).await.is_err()
+            || reader.read_to_end(&mut bytes).await.is_err()
+        {
+            return;
+        }
+        // keep back the start of a delimiter or of a character
+        let len = registration
+            .framing
+            .partial_len(&bytes, registration.encoding);
+        let len = registration.encoding.complete_len(&bytes[..len]);
+        if len == 0 {
+            return;
+        }
+        let line_start = self.position;
+        self.position += len as u64;
+
+        let line = registration.encoding.transcode(&bytes[..len]).into_owned();
+        match &registration.multiline {
+            Some(multiline) => {
+                if let Some(record) = self.record.push(multiline, line, line_start) {
+                    deliver(path, None, record, registration, false).await;
+                }
+                if let Some(record) = self.record.take_expired(Duration::ZERO) {
+                    deliver(path, None, record, registration, true).await;
+                }
+            }
+            None => deliver(path, None, line, registration, true).await,
+        }
+        self.commit(path, registration).await;
+    }
+
+    // save the offset once the callbacks of the read lines have returned.
+    async fn commit(&mut self, path: &Path, registration: &Registration) {
+        let (Some(offset_store), Some(reader)) = (&registration.offset_store, &mut self.reader)
+        else {
+            return;
+        };
+        // the offset is saved once the callbacks of the queued events have returned
+        if let Some(events) = &self.events {
+            events.drained().await;
+        }
+        // the lines waiting in a batch are read again after a restart
+        if registration
+            .batch
+            .as_ref()
+            .is_some_and(|batch| !batch.is_empty())
+        {
+            return;
+        }
+        // the lines of an incomplete record are read again after a restart
+        let offset = self.record.start().unwrap_or(self.position);
+        if self.committed == Some(offset) {
+            return;
+        }
+
+        let result = match Fingerprint::read(reader, self.file_id, offset).await {
+            Ok(fingerprint) => {
+                let checkpoint = Checkpoint {
+                    fingerprint,
+                    offset,
+                };
+                let offset_store = Arc::clone(offset_store);
+                let key = checkpoint_key(path, registration);
+                rt::spawn_blocking(move || offset_store.commit(&key, checkpoint)).await
+            }
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(()) => self.committed = Some(offset),
+            Err(e) => notify_offset_error(path, &registration.callback, e).await,
+        }
+    }
+}
+
+fn checkpoint_key(path: &Path, registration: &Registration) -> CheckpointKey {
+    CheckpointKey {
+        path: path.to_owned(),
+        name: registration.checkpoint_name.clone(),
+    }
+}
+
+// the offset to resume from, saved by a previous run.
+async fn load_checkpoint(path: &Path, registration: &Registration) -> Option<Checkpoint> {
+    let offset_store = Arc::clone(registration.offset_store.as_ref()?);
+    let key = checkpoint_key(path, registration);
+    match rt::spawn_blocking(move || offset_store.load(&key)).await {
+        Ok(checkpoint) => checkpoint,
+        Err(e) => {
+            notify_offset_error(path, &registration.callback, e).await;
+            None
+        }
+    }
+}
+
+// deliver the lines from `offset` of the newest rotated copy of the file starting like `head`,
+// then the lines of the newer copies. the incomplete record is delivered at the end.
+async fn catch_up(
+    path: &Path,
+    head: &Fingerprint,
+    offset: u64,
+    record: &mut Record,
+    registration: &Registration,
+) {
+    let rotated = archive::rotated_files(path).await;
+    let mut found = None;
+    for (i, archive) in rotated.iter().enumerate() {
+        match archive::read_head(archive, head.head_len).await {
+            Ok(data) if head.matches_head(&data) => {
+                found = Some(i);
+                break;
+            }
+            _ => {}
+        }
+    }
+    let Some(found) = found else {
+        return;
+    };
+
+    // the oldest copy first
+    for (i, archive) in rotated[..=found].iter().enumerate().rev() {
+        let offset = if i == found { offset } else { 0 };
+        read_archive(path, archive, offset, record, registration).await;
+    }
+    if let Some(record) = record.take_expired(Duration::ZERO) {
+        deliver(path, None, record, registration, false).await;
+    }
+}
+
+// deliver the lines of a rotated copy from `offset`, read one chunk at a time. the copy is
+// complete, so its last line doesn't wait for a newline.
+async fn read_archive(
+    path: &Path,
+    archive: &Path,
+    offset: u64,
+    record: &mut Record,
+    registration: &Registration,
+) {
+    let notify_error = |e| {
+        let log_error = LogError {
+            kind: ErrorKind::FileOpenError(e),
+        };
+        let mut log_event = LogEvent::new(path.to_owned(), None, Some(log_error));
+        log_event.archive = Some(archive.to_owned());
+        (registration.callback)(log_event)
+    };
+    let mut chunks = match archive::Archive::open(archive, offset).await {
+        Ok(chunks) => chunks,
+        Err(e) => return notify_error(e).await,
+    };
+
+    // the position in the copy of the bytes not read yet, i.e. a partial line of the chunk
+    let mut position = offset;
+    let mut rest = Vec::new();
+    let archive = Some(archive);
+    loop {
+        let chunk = match chunks.read_chunk().await {
+            Ok(chunk) if chunk.is_empty() => break,
+            Ok(chunk) => chunk,
+            Err(e) => return notify_error(e).await,
+        };
+        rest.extend_from_slice(&chunk);
+        // the lines start at positions relative to the chunk, which is fine as the record of
+        // the copies is delivered at the end of the catch up
+        let mut read = 0;
+        let mut reader = Cursor::new(rest);
+        if !read_lines(&mut reader, &mut read, record, path, archive, registration).await {
+            return;
+        }
+        rest = reader.into_inner();
+        rest.drain(..read as usize);
+        position += read;
+    }
+
+    let len = (registration.framing).partial_len(&rest, registration.encoding);
+    if len > 0 {
+        let line = registration.encoding.transcode(&rest[..len]).into_owned();
+        push_line(path, archive, line, position, record, registration).await;
+    }
+}
+
+pub(crate) async fn notify_offset_error(path: &Path, callback: &LogCallback, e: std::io::Error) {
+    let log_error = LogError {
+        kind: ErrorKind::OffsetStoreError(e),
+    };
+    callback(LogEvent::new(path.to_owned(), None, Some(log_error))).await;
+}
+
+// read every complete line (or record of the framing) from `position` to the end of the file
+// and deliver each one to the callback in order. `position` is advanced past each delivered
+// line, so a trailing partial line is read again on the next event. returns false, after
+// notifying the error, if a record can't be framed.
+async fn read_lines<R: Reader>(
+    reader: &mut R,
+    position: &mut u64,
+    record: &mut Record,
+    path: &Path,
+    archive: Option<&Path>,
+    registration: &Registration,
+) -> bool {
+    // seek from *position
+    if let Err(e) = reader.seek(SeekFrom::Start(*position)).await {
+        let log_error = LogError {
+            kind: ErrorKind::FileSeekError(e),
+        };
+        (registration.callback)(LogEvent::new(path.to_owned(), None, Some(log_error))).await;
+        return true;
+    }
+
+    let mut frame = Vec::new();
+    loop {
+        frame.clear();
+        // stop at EOF or when only a partial record has been written so far
+        let framing = &registration.framing;
+        let len = match framing
+            .read(reader, &mut frame, registration.encoding)
+            .await
+        {
+            Ok(Some(len)) => len,
+            // e.g. a length over the maximum frame size
+            Err(e) if e.kind() == std::io::ErrorKind::InvalidData => {
+                let log_error = LogError {
+                    kind: ErrorKind::FrameError(e),
+                };
+                let log_event = LogEvent::new(path.to_owned(), None, Some(log_error));
+                (registration.callback)(log_event).await;
+                return false;
+            }
+            _ => return true,
+        };
+        let line_start = *position;
+        *position += len;
+
+        let line = registration.encoding.transcode(&frame).into_owned();
+        push_line(path, archive, line, line_start, record, registration).await;
+    }
+}
+
+// deliver a line, or add it to the record of a multiline registration.
+async fn push_line(
+    path: &Path,
+    archive: Option<&Path>,
+    line: Vec<u8>,
+    line_start: u64,
+    record: &mut Record,
+    registration: &Registration,
+) {
+    match &registration.multiline {
+        Some(multiline) => {
+            if let Some(record) = record.push(multiline, line, line_start) {
+                deliver(path, archive, record, registration, false).await;
+            }
+        }
+        None => deliver(path, archive, line, registration, false).await,
+    }
+}
+
+// notify a line or a record to the callback, if it matches the filter. the line is parsed
+// first so that the filter can test the fields.
+async fn deliver(
+    path: &Path,
+    archive: Option<&Path>,
+    line: Vec<u8>,
+    registration: &Registration,
+    partial: bool,
+) {
+    let mut log_event = new_line_event(path, line, registration);
+    log_event.partial = partial;
+    log_event.archive = archive.map(Path::to_owned);
+    if let Some(filter) = &registration.filter {
+        // a line which isn't decoded is matched with the invalid sequences replaced
+        let line = match (&log_event.line, &log_event.bytes) {
+            (Some(line), _) => Cow::Borrowed(line.as_str()),
+            (None, bytes) => String::from_utf8_lossy(bytes.as_deref().unwrap_or_default()),
+        };
+        match filter.find(&line, log_event.fields.as_ref()) {
+            Some(matches) => log_event.matches = matches,
+            None => return,
+        }
+    }
+    (registration.callback)(log_event).await;
+}
+
+// the event of a line decoded and parsed by the registration.
+fn new_line_event(path: &Path, bytes: Vec<u8>, registration: &Registration) -> LogEvent {
+    let mut log_event = LogEvent::new(path.to_owned(), None, None);
+
+    let line = match registration.encoding {
+        Encoding::Raw => {
+            log_event.bytes = Some(bytes);
+            return log_event;
+        }
+        Encoding::Utf8Lossy => match String::from_utf8(bytes) {
+            Ok(line) => line,
+            Err(e) => String::from_utf8_lossy(e.as_bytes()).into_owned(),
+        },
+        // the other encodings have been transcoded into UTF-8
+        _ => match String::from_utf8(bytes) {
+            Ok(line) => line,
+            Err(e) => {
+                log_event.log_error = Some(LogError {
+                    kind: ErrorKind::InvalidUtf8Error(e.utf8_error()),
+                });
+                log_event.bytes = Some(e.into_bytes());
+                return log_event;
+            }
+        },
+    };
+
+    if let Some(parser) = &registration.parser {
+        match parser.parse(&line) {
+            Ok(fields) => log_event.fields = Some(fields),
+            Err(e) => {
+                log_event.log_error = Some(LogError {
+                    kind: ErrorKind::LineParseError(e),
+                })
+            }
+        }
+    }
+
+    #[cfg(feature = "json")]
+    if registration.json {
+        match serde_json::from_str(&line) {
+            Ok(json) => log_event.json = Some(json),
+            Err(e) => {
+                log_event.log_error = Some(LogError {
+                    kind: ErrorKind::JsonParseError(e),
+                })
+            }
+        }
+    }
+
+    log_event.line = Some(line);
+    log_event
+}
+
+// the initial position of an existing file.
+async fn find_start_position(reader: &mut BufReader<File>, registration: &Registration) -> u64 {
+    let (framing, encoding) = (&registration.framing, registration.encoding);
+    match registration.start_position {
+        StartPosition::Beginning => 0,
+        StartPosition::End => find_last_records(reader, 0, framing, encoding).await,
+        StartPosition::LastLines(n) => find_last_records(reader, n, framing, encoding).await,
+        StartPosition::Offset(offset) => {
+            let len = reader.get_ref().metadata().await.map_or(0, |m| m.len());
+            offset.min(len)
+        }
+        StartPosition::Since(since) => find_since(reader, since, framing, encoding).await,
+    }
+}
+
+// find the position of the last `n` complete records of the framing.
+async fn find_last_records(
+    reader: &mut BufReader<File>,
+    n: usize,
+    framing: &Framing,
+    encoding: Encoding,
+) -> u64 {
+    if framing.is_newline() {
+        return find_last_lines(reader, n, encoding.newline()).await;
+    }
+    if let Some(size) = framing.fixed_size_of() {
+        let len = reader.get_ref().metadata().await.map_or(0, |m| m.len());
+        let end = len / size as u64 * size as u64;
+        return end.saturating_sub(n as u64 * size as u64);
+    }
+
+    // the other records can only be found from the beginning of the file
+    let mut starts = VecDeque::from([0]);
+    let mut position = 0;
+    let mut frame = Vec::new();
+    if reader.seek(SeekFrom::Start(0)).await.is_err() {
+        return 0;
+    }
+    while let Ok(Some(len)) = framing.read(reader, &mut frame, encoding).await {
+        position += len;
+        starts.push_back(position);
+        if starts.len() > n + 1 {
+            starts.pop_front();
+        }
+        frame.clear();
+    }
+    starts[0]
+}
+
+// size of the blocks read backwards from the end of the file.
+const SCAN_BLOCK_SIZE: u64 = 8 * 1024;
+
+// find the position of the last `n` complete lines. for 0, the end of the last complete line.
+//
+// the file is read backwards from the end in blocks, so the time doesn't depend on the size of
+// the file. the start of the n-th last line is right after the (n + 1)-th last newline.
+pub(crate) async fn find_last_lines(reader: &mut BufReader<File>, n: usize, newline: &[u8]) -> u64 {
+    let Ok(metadata) = reader.get_ref().metadata().await else {
+        return 0;
+    };
+    // the blocks are aligned on the length of the newline
+    let unit = newline.len() as u64;
+    let mut block = vec![0; SCAN_BLOCK_SIZE as usize];
+    let mut block_end = metadata.len() / unit * unit;
+    let mut newlines = 0;
+
+    while block_end > 0 {
+        let block_start = block_end.saturating_sub(SCAN_BLOCK_SIZE);
+        let block = &mut block[..(block_end - block_start) as usize];
+        if reader.seek(SeekFrom::Start(block_start)).await.is_err()
+            || reader.read_exact(block).await.is_err()
+        {
+            return 0;
+        }
+
+        for (i, chunk) in block.chunks_exact(unit as usize).enumerate().rev() {
+            if chunk == newline {
+                newlines += 1;
+                if newlines > n {
+                    return block_start + (i as u64 + 1) * unit;
+                }
+            }
+        }
+        block_end = block_start;
+    }
+
+    0
+}
+
+// find the position of the first line with a timestamp at or after `since`. the lines without
+// a timestamp are skipped.
+async fn find_since(
+    reader: &mut BufReader<File>,
+    since: SystemTime,
+    framing: &Framing,
+    encoding: Encoding,
+) -> u64 {
+    let mut line = Vec::new();
+    let mut current_position = 0;
+
+    if reader.seek(SeekFrom::Start(0)).await.is_err() {
+        return 0;
+    }
+    while let Ok(Some(len)) = framing.read(reader, &mut line, encoding).await {
+        let timestamp = line_timestamp(&String::from_utf8_lossy(&encoding.transcode(&line)));
+        if timestamp.is_some_and(|timestamp| timestamp >= since) {
+            break;
+        }
+        current_position += len;
+        line.clear();
+    }
+
+    current_position
+}
+
+// the RFC 3339 timestamp at the beginning of a line, e.g. "2023-05-01T10:00:00Z" or
+// "[2023-05-01 10:00:00.123]". a timestamp without a time zone is taken as UTC.
+fn line_timestamp(line: &str) -> Option<SystemTime> {
+    let mut tokens = line.trim_start_matches('[').split_whitespace();
+    let date = tokens.next()?.trim_end_matches(']');
+    if let Ok(timestamp) = humantime::parse_rfc3339_weak(date) {
+        return Some(timestamp);
+    }
+    let time = tokens.next()?.trim_end_matches(']');
+    humantime::parse_rfc3339_weak(&format!("{} {}", date, time)).ok()
+}
+
+#[cfg(all(test, feature = "async-std"))]
+mod tests {
+
+    use super::{find_last_lines, line_timestamp};
+    use async_std::{fs::remove_file, fs::File, io::BufReader, prelude::*};
+
+    #[async_std::test]
+    async fn test_find_last_lines() {
+        //
+        let filepath = "test-log.txt";
+
+        let _ = remove_file(filepath).await;
+
+        let mut file = File::create(filepath).await.unwrap();
+
+        file.write_all(b"0\n").await.unwrap();
+        file.write_all(b"1\n").await.unwrap();
+        file.write_all(b"2\n").await.unwrap();
+        file.write_all(b"3\n").await.unwrap();
+        file.flush().await.unwrap();
+
+        let ofile = File::open(&filepath).await.unwrap();
+        let mut reader = BufReader::new(ofile);
+        let position = find_last_lines(&mut reader, 1, b"\n").await;
+
+        // assert last line position
+        assert_eq!(position, 6);
+        assert_eq!(find_last_lines(&mut reader, 0, b"\n").await, 8);
+        assert_eq!(find_last_lines(&mut reader, 3, b"\n").await, 2);
+        assert_eq!(find_last_lines(&mut reader, 10, b"\n").await, 0);
+
+        let mut line = String::new();
+        reader
+            .seek(std::io::SeekFrom::Start(position))
+            .await
+            .unwrap();
+        reader.read_line(&mut line).await.unwrap();
+        // assert last line
+        assert_eq!(line, "3\n");
+
+        let _ = remove_file(filepath).await; // Remove the file if it exists
+    }
+
+    #[async_std::test]
+    async fn test_find_last_lines_blocks() {
+        let filepath = "test-log-blocks.txt";
+
+        let _ = remove_file(filepath).await;
+
+        // lines of 100 bytes spanning several blocks, and a partial line
+        let mut file = File::create(filepath).await.unwrap();
+        for _ in 0..300 {
+            file.write_all(&[b'a'; 99]).await.unwrap();
+            file.write_all(b"\n").await.unwrap();
+        }
+        file.write_all(b"partial").await.unwrap();
+        file.flush().await.unwrap();
+
+        let mut reader = BufReader::new(File::open(&filepath).await.unwrap());
+        assert_eq!(find_last_lines(&mut reader, 0, b"\n").await, 30000);
+        assert_eq!(find_last_lines(&mut reader, 1, b"\n").await, 29900);
+        assert_eq!(find_last_lines(&mut reader, 150, b"\n").await, 15000);
+        assert_eq!(find_last_lines(&mut reader, 300, b"\n").await, 0);
+        assert_eq!(find_last_lines(&mut reader, 1000, b"\n").await, 0);
+
+        let _ = remove_file(filepath).await;
+    }
+
+    #[async_std::test]
+    async fn test_find_last_lines_utf16() {
+        let filepath = "test-log-utf16.txt";
+
+        let _ = remove_file(filepath).await;
+
+        // "a\n\u{a0a}\n" in UTF-16LE, with a '\n' byte which isn't a newline
+        let mut file = File::create(filepath).await.unwrap();
+        file.write_all(b"a\0\n\0\x0a\x0a\n\0").await.unwrap();
+        file.flush().await.unwrap();
+
+        let mut reader = BufReader::new(File::open(&filepath).await.unwrap());
+        assert_eq!(find_last_lines(&mut reader, 0, b"\n\0").await, 8);
+        assert_eq!(find_last_lines(&mut reader, 1, b"\n\0").await, 4);
+        assert_eq!(find_last_lines(&mut reader, 2, b"\n\0").await, 0);
+
+        let _ = remove_file(filepath).await;
+    }
+
+    #[test]
+    fn test_line_timestamp() {
+        let timestamp = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1682935200);
+        assert_eq!(
+            line_timestamp("2023-05-01T10:00:00Z INFO started"),
+            Some(timestamp)
+        );
+        assert_eq!(
+            line_timestamp("[2023-05-01 10:00:00] INFO started"),
+            Some(timestamp)
+        );
+        assert_eq!(line_timestamp("    at com.example.Main"), None);
+    }
+}
@@ -1,3 +1,5 @@
+#![cfg(feature = "async-std")]
+
 use async_log_watch::{LogEvent, LogWatcher};
 
 use async_std::{
@@ -7,6 +9,7 @@ use async_std::{
     task::{self, sleep},
 };
 
+#[async_std::test]
 async fn test_log_watcher() {
     let mut log_watcher = LogWatcher::new();
 
@@ -30,7 +33,8 @@ async fn test_log_watcher() {
             },
             None,
         )
-        .await;
+        .await
+        .unwrap();
 
     task::spawn(async move {
         log_watcher
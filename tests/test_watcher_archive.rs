@@ -0,0 +1,156 @@
+#![cfg(feature = "async-std")]
+
+use async_log_watch::{LogEvent, LogWatcher, RegisterOptions};
+
+use async_std::{
+    fs::{remove_file, File},
+    sync::Mutex,
+    task::sleep,
+};
+
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(feature = "gzip")]
+async fn append(file_path: &str, data: &str) {
+    use async_std::{fs::OpenOptions, io::prelude::*};
+
+    let mut file = OpenOptions::new()
+        .append(true)
+        .open(file_path)
+        .await
+        .unwrap();
+    file.write_all(data.as_bytes()).await.unwrap();
+    file.flush().await.unwrap();
+}
+
+// start monitoring, write the lines and return the delivered lines with their archive.
+async fn watch_once(
+    log_path: &str,
+    options: RegisterOptions,
+    write: impl FnOnce(),
+) -> Vec<(String, Option<String>)> {
+    let mut log_watcher = LogWatcher::new();
+
+    let lines = Arc::new(Mutex::new(Vec::new()));
+    let lines_clone = lines.clone();
+    log_watcher
+        .register_with_options(
+            log_path,
+            move |log_event: LogEvent| {
+                let lines = lines_clone.clone();
+                async move {
+                    if let Some(line) = log_event.get_line() {
+                        let archive = (log_event.archive())
+                            .map(|archive| archive.file_name().unwrap().to_string_lossy());
+                        lines
+                            .lock()
+                            .await
+                            .push((line.clone(), archive.map(|archive| archive.into_owned())));
+                    }
+                }
+            },
+            options,
+        )
+        .await
+        .unwrap();
+
+    let monitor_handle = log_watcher.start(Duration::from_millis(100)).await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+    write();
+    sleep(Duration::from_millis(500)).await;
+
+    monitor_handle.shutdown().await.unwrap();
+    let lines = lines.lock().await.clone();
+    lines
+}
+
+#[cfg(feature = "gzip")]
+#[async_std::test]
+async fn log_watcher_archive_backfill_test() {
+    use async_log_watch::FileOffsetStore;
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::Write;
+
+    // ready for log file
+    let log_path = "test_log_archive.txt";
+    let old_archive_path = "test_log_archive.txt.2.gz";
+    let archive_path = "test_log_archive.txt.1.gz";
+    let offsets_path = "test_log_archive.json";
+    for path in [log_path, old_archive_path, archive_path, offsets_path] {
+        let _ = remove_file(path).await; // remove the files if they exist
+    }
+    let compress = |path: &str, data: &[u8]| {
+        let mut encoder = GzEncoder::new(std::fs::File::create(path).unwrap(), Compression::fast());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap();
+    };
+    compress(old_archive_path, b"old 1\n");
+    File::create(log_path).await.unwrap();
+
+    let offset_store = Arc::new(FileOffsetStore::open(offsets_path).unwrap());
+    let options = || RegisterOptions::new().with_offset_store(offset_store.clone(), "lines");
+    let lines = watch_once(log_path, options(), || {
+        std::fs::write(log_path, "line 1\n").unwrap();
+    })
+    .await;
+    assert_eq!(lines, vec![("line 1".to_owned(), None)]);
+
+    // written and rotated into a compressed copy while the watcher is stopped
+    append(log_path, "line 2\nline 3").await;
+    compress(archive_path, &std::fs::read(log_path).unwrap());
+    remove_file(log_path).await.unwrap();
+    std::fs::write(log_path, "new 1\n").unwrap();
+
+    // the missed lines are read from the archive, then the new file from the beginning
+    let lines = watch_once(log_path, options(), || {
+        std::fs::write(log_path, "new 1\nnew 2\n").unwrap();
+    })
+    .await;
+    let archive = Some(archive_path.to_owned());
+    assert_eq!(
+        lines,
+        vec![
+            ("line 2".to_owned(), archive.clone()),
+            ("line 3".to_owned(), archive),
+            ("new 1".to_owned(), None),
+            ("new 2".to_owned(), None),
+        ]
+    );
+
+    // remove test files
+    for path in [log_path, old_archive_path, archive_path, offsets_path] {
+        remove_file(path).await.unwrap();
+    }
+}
+
+#[async_std::test]
+async fn log_watcher_archive_copytruncate_test() {
+    // ready for log file
+    let log_path = "test_log_archive_copytruncate.txt";
+    let rotated_path = "test_log_archive_copytruncate.txt.1";
+    let _ = remove_file(log_path).await; // remove the files if they exist
+    let _ = remove_file(rotated_path).await;
+    File::create(log_path).await.unwrap();
+
+    // the line written just before the copy is read from the copy if the truncation is seen
+    // first
+    let lines = watch_once(log_path, RegisterOptions::new(), || {
+        std::fs::write(log_path, "line 1\n").unwrap();
+        std::thread::sleep(Duration::from_millis(300));
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(log_path)
+            .unwrap();
+        std::io::Write::write_all(&mut file, b"line 2\n").unwrap();
+        std::fs::copy(log_path, rotated_path).unwrap();
+        std::fs::write(log_path, "new\n").unwrap();
+    })
+    .await;
+    let lines: Vec<_> = lines.into_iter().map(|(line, _)| line).collect();
+    assert_eq!(lines, vec!["line 1", "line 2", "new"]);
+
+    // remove test files
+    remove_file(log_path).await.unwrap();
+    remove_file(rotated_path).await.unwrap();
+}
@@ -0,0 +1,270 @@
+#![cfg(any(feature = "async-std", feature = "tokio"))]
+
+use async_log_watch::{LogEvent, LogWatcher, Overflow, WatcherConfig};
+
+#[cfg(feature = "async-std")]
+use async_std::{
+    fs::{remove_file, File},
+    io::prelude::*,
+    sync::Mutex,
+    task::sleep,
+};
+#[cfg(feature = "tokio")]
+use tokio::{
+    fs::{remove_file, File},
+    io::AsyncWriteExt,
+    sync::Mutex,
+    time::sleep,
+};
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg_attr(feature = "async-std", async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test(flavor = "multi_thread"))]
+async fn log_watcher_max_in_flight_test() {
+    // ready for log files
+    let log_paths = ["test_log_in_flight_1.txt", "test_log_in_flight_2.txt"];
+    let mut files = Vec::new();
+    for log_path in log_paths {
+        let _ = remove_file(log_path).await; // remove the file if it exists
+        files.push(File::create(log_path).await.unwrap());
+    }
+
+    let mut log_watcher = LogWatcher::with_config(WatcherConfig::new().with_max_in_flight(1));
+
+    let running = Arc::new(AtomicUsize::new(0));
+    let max_running = Arc::new(AtomicUsize::new(0));
+    let delivered = Arc::new(AtomicUsize::new(0));
+    for log_path in log_paths {
+        let running = running.clone();
+        let max_running = max_running.clone();
+        let delivered = delivered.clone();
+        log_watcher
+            .register(
+                log_path,
+                move |_: LogEvent| {
+                    let running = running.clone();
+                    let max_running = max_running.clone();
+                    let delivered = delivered.clone();
+                    async move {
+                        let count = running.fetch_add(1, Ordering::SeqCst) + 1;
+                        max_running.fetch_max(count, Ordering::SeqCst);
+                        sleep(Duration::from_millis(200)).await;
+                        running.fetch_sub(1, Ordering::SeqCst);
+                        delivered.fetch_add(1, Ordering::SeqCst);
+                    }
+                },
+                None,
+            )
+            .await
+            .unwrap();
+    }
+
+    // start monitoring
+    let monitor_handle = log_watcher.start(Duration::from_millis(100)).await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+
+    for file in files.iter_mut() {
+        file.write_all(b"line 1\n").await.unwrap();
+        file.flush().await.unwrap();
+    }
+    sleep(Duration::from_millis(1000)).await;
+
+    // the callbacks of both files ran one at a time
+    monitor_handle.shutdown().await.unwrap();
+    assert_eq!(delivered.load(Ordering::SeqCst), 2);
+    assert_eq!(max_running.load(Ordering::SeqCst), 1);
+
+    // remove test log files
+    for log_path in log_paths {
+        remove_file(log_path).await.unwrap();
+    }
+}
+
+// write the lines in separate events to a slow callback, and return the delivered lines and the
+// number of dropped lines.
+async fn overflow_lines(
+    log_path: &str,
+    overflow: Overflow,
+    lines: &[String],
+) -> (Vec<String>, u64) {
+    let _ = remove_file(log_path).await; // remove the file if it exists
+    let mut file = File::create(log_path).await.unwrap();
+
+    let config = WatcherConfig::new()
+        .with_queue_capacity(1)
+        .with_overflow(overflow);
+    let mut log_watcher = LogWatcher::with_config(config);
+
+    let delivered = Arc::new(Mutex::new(Vec::new()));
+    let dropped = Arc::new(AtomicU64::new(0));
+    let delivered_clone = delivered.clone();
+    let dropped_clone = dropped.clone();
+    log_watcher
+        .register(
+            log_path,
+            move |log_event: LogEvent| {
+                let delivered = delivered_clone.clone();
+                let dropped = dropped_clone.clone();
+                async move {
+                    if let Some(count) = log_event.dropped() {
+                        dropped.fetch_add(count, Ordering::SeqCst);
+                    }
+                    if let Some(line) = log_event.get_line() {
+                        // a slow callback
+                        sleep(Duration::from_millis(100)).await;
+                        delivered.lock().await.push(line.clone());
+                    }
+                }
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+    // start monitoring
+    let monitor_handle = log_watcher.start(Duration::from_millis(100)).await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+
+    // more lines than the queue can hold while the callback is running
+    for line in lines {
+        file.write_all(format!("{}\n", line).as_bytes())
+            .await
+            .unwrap();
+        file.flush().await.unwrap();
+        sleep(Duration::from_millis(20)).await;
+    }
+    sleep(Duration::from_millis(1500)).await;
+    monitor_handle.shutdown().await.unwrap();
+
+    // remove test log file
+    remove_file(log_path).await.unwrap();
+
+    let delivered = delivered.lock().await.clone();
+    (delivered, dropped.load(Ordering::SeqCst))
+}
+
+#[cfg_attr(feature = "async-std", async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test(flavor = "multi_thread"))]
+async fn log_watcher_overflow_test() {
+    let lines: Vec<String> = (1..=10).map(|i| format!("line {}", i)).collect();
+
+    // the lines are dropped, and every line is either delivered or counted
+    let (delivered, dropped) =
+        overflow_lines("test_log_overflow_newest.txt", Overflow::DropNewest, &lines).await;
+    assert!(dropped > 0);
+    assert_eq!(delivered.len() as u64 + dropped, 10);
+    assert_eq!(delivered[0], "line 1");
+    // in the order of the lines
+    let positions: Vec<_> = delivered
+        .iter()
+        .map(|line| lines.iter().position(|l| l == line).unwrap())
+        .collect();
+    assert!(positions.windows(2).all(|w| w[0] < w[1]));
+
+    // the newest line is kept
+    let (delivered, dropped) =
+        overflow_lines("test_log_overflow_oldest.txt", Overflow::DropOldest, &lines).await;
+    assert!(dropped > 0);
+    assert_eq!(delivered.len() as u64 + dropped, 10);
+    assert_eq!(delivered.last().unwrap(), "line 10");
+
+    // only the repeated lines are dropped
+    let mut repeated = vec!["same".to_owned(); 9];
+    repeated.push("last".to_owned());
+    let (delivered, dropped) = overflow_lines(
+        "test_log_overflow_coalesce.txt",
+        Overflow::Coalesce,
+        &repeated,
+    )
+    .await;
+    assert!(dropped > 0);
+    assert_eq!(delivered.len() as u64 + dropped, 10);
+    assert_eq!(delivered.last().unwrap(), "last");
+
+    // no line is lost when the reading waits
+    let (delivered, dropped) =
+        overflow_lines("test_log_overflow_block.txt", Overflow::Block, &lines).await;
+    assert_eq!(dropped, 0);
+    assert_eq!(delivered, lines);
+}
+
+#[cfg_attr(feature = "async-std", async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test(flavor = "multi_thread"))]
+async fn log_watcher_overflow_block_test() {
+    // ready for log files
+    let slow_path = "test_log_block_slow.txt";
+    let fast_path = "test_log_block_fast.txt";
+    let _ = remove_file(slow_path).await; // remove the files if they exist
+    let _ = remove_file(fast_path).await;
+    let mut slow_file = File::create(slow_path).await.unwrap();
+    let mut fast_file = File::create(fast_path).await.unwrap();
+
+    let config = WatcherConfig::new()
+        .with_queue_capacity(1)
+        .with_overflow(Overflow::Block);
+    let mut log_watcher = LogWatcher::with_config(config);
+
+    let slow_count = Arc::new(AtomicUsize::new(0));
+    let slow_count_clone = slow_count.clone();
+    log_watcher
+        .register(
+            slow_path,
+            move |log_event: LogEvent| {
+                let slow_count = slow_count_clone.clone();
+                async move {
+                    if log_event.get_line().is_some() {
+                        sleep(Duration::from_millis(500)).await;
+                        slow_count.fetch_add(1, Ordering::SeqCst);
+                    }
+                }
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+    let fast_lines = Arc::new(Mutex::new(Vec::new()));
+    let fast_lines_clone = fast_lines.clone();
+    log_watcher
+        .register(
+            fast_path,
+            move |log_event: LogEvent| {
+                let fast_lines = fast_lines_clone.clone();
+                async move {
+                    if let Some(line) = log_event.get_line() {
+                        fast_lines.lock().await.push(line.clone());
+                    }
+                }
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+    // start monitoring
+    let monitor_handle = log_watcher.start(Duration::from_millis(100)).await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+
+    // the reading of the slow file waits for room in its queue
+    slow_file.write_all(b"1\n2\n3\n4\n").await.unwrap();
+    slow_file.flush().await.unwrap();
+    sleep(Duration::from_millis(200)).await;
+
+    // while the other file goes on
+    fast_file.write_all(b"fast 1\n").await.unwrap();
+    fast_file.flush().await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+    assert_eq!(*fast_lines.lock().await, vec!["fast 1"]);
+    assert!(slow_count.load(Ordering::SeqCst) < 4);
+
+    // every line of the slow file is delivered before the shutdown returns
+    monitor_handle.shutdown().await.unwrap();
+    assert_eq!(slow_count.load(Ordering::SeqCst), 4);
+
+    // remove test log files
+    remove_file(slow_path).await.unwrap();
+    remove_file(fast_path).await.unwrap();
+}
@@ -0,0 +1,222 @@
+#![cfg(feature = "async-std")]
+
+use async_log_watch::{
+    CheckpointKey, Delivery, LogEvent, LogWatcher, MemoryOffsetStore, OffsetStore, RegisterOptions,
+    WatcherConfig,
+};
+
+use async_std::{
+    fs::{remove_file, File},
+    io::prelude::*,
+    sync::Mutex,
+    task::sleep,
+};
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+fn lines_of(log_events: &[LogEvent]) -> Vec<String> {
+    log_events
+        .iter()
+        .filter_map(|log_event| log_event.get_line().cloned())
+        .collect()
+}
+
+#[async_std::test]
+async fn log_watcher_batch_test() {
+    // ready for log file
+    let log_path = "test_log_batch.txt";
+    let _ = remove_file(log_path).await; // remove the file if it exists
+    let mut file = File::create(log_path).await.unwrap();
+
+    let mut log_watcher = LogWatcher::new();
+
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let batches_clone = batches.clone();
+    log_watcher
+        .register_batch(
+            log_path,
+            3,
+            1024,
+            Duration::from_millis(500),
+            move |log_events: Vec<LogEvent>| {
+                let batches = batches_clone.clone();
+                async move {
+                    batches.lock().await.push(lines_of(&log_events));
+                }
+            },
+        )
+        .await
+        .unwrap();
+
+    // start monitoring
+    let monitor_handle = log_watcher.start(Duration::from_millis(100)).await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+
+    file.write_all(b"line 1\nline 2\nline 3\nline 4\nline 5\nline 6\nline 7\n")
+        .await
+        .unwrap();
+    file.flush().await.unwrap();
+    sleep(Duration::from_millis(200)).await;
+
+    // the full batches are delivered right away
+    assert_eq!(
+        *batches.lock().await,
+        vec![
+            vec!["line 1", "line 2", "line 3"],
+            vec!["line 4", "line 5", "line 6"],
+        ]
+    );
+
+    // the rest once it has waited for the max wait
+    sleep(Duration::from_millis(800)).await;
+    assert_eq!(batches.lock().await.len(), 3);
+    assert_eq!(batches.lock().await[2], vec!["line 7"]);
+
+    // a batch reaching the max bytes
+    file.write_all(&[b'a'; 2000]).await.unwrap();
+    file.write_all(b"\nline 8\n").await.unwrap();
+    file.flush().await.unwrap();
+    sleep(Duration::from_millis(200)).await;
+    assert_eq!(batches.lock().await.len(), 4);
+    assert_eq!(batches.lock().await[3].len(), 1);
+
+    // the waiting batch is delivered at the shutdown
+    monitor_handle.shutdown().await.unwrap();
+    assert_eq!(batches.lock().await[4], vec!["line 8"]);
+
+    // remove test log file
+    remove_file(log_path).await.unwrap();
+}
+
+#[async_std::test]
+async fn log_watcher_batch_offset_test() {
+    // ready for log file
+    let log_path = "test_log_batch_offset.txt";
+    let _ = remove_file(log_path).await; // remove the file if it exists
+    let mut file = File::create(log_path).await.unwrap();
+    let absolute_path = std::env::current_dir().unwrap().join(log_path);
+
+    let mut log_watcher = LogWatcher::new();
+    let offset_store = Arc::new(MemoryOffsetStore::new());
+
+    let key = Arc::new(CheckpointKey {
+        path: absolute_path,
+        name: "batches".to_owned(),
+    });
+
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let batches_clone = batches.clone();
+    let offset_store_clone = offset_store.clone();
+    let key_clone = key.clone();
+    log_watcher
+        .register_batch_with_options(
+            log_path,
+            10,
+            1024,
+            Duration::from_millis(500),
+            move |log_events: Vec<LogEvent>| {
+                let batches = batches_clone.clone();
+                let offset_store = offset_store_clone.clone();
+                let key = key_clone.clone();
+                async move {
+                    // the offset hasn't advanced before the callback returns
+                    assert_eq!(offset_store.load(&key).unwrap(), None);
+                    batches.lock().await.push(lines_of(&log_events));
+                }
+            },
+            RegisterOptions::new().with_offset_store(offset_store.clone(), "batches"),
+        )
+        .await
+        .unwrap();
+
+    // start monitoring
+    let monitor_handle = log_watcher.start(Duration::from_millis(100)).await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+
+    file.write_all(b"line 1\nline 2\n").await.unwrap();
+    file.flush().await.unwrap();
+    sleep(Duration::from_millis(200)).await;
+
+    // the lines waiting in the batch aren't saved
+    assert!(batches.lock().await.is_empty());
+    assert_eq!(offset_store.load(&key).unwrap(), None);
+
+    sleep(Duration::from_millis(800)).await;
+    assert_eq!(*batches.lock().await, vec![vec!["line 1", "line 2"]]);
+    let checkpoint = offset_store.load(&key).unwrap().unwrap();
+    assert_eq!(checkpoint.offset, 14);
+
+    monitor_handle.shutdown().await.unwrap();
+
+    // remove test log file
+    remove_file(log_path).await.unwrap();
+}
+
+#[async_std::test]
+async fn log_watcher_batch_order_test() {
+    // ready for log file
+    let log_path = "test_log_batch_order.txt";
+    let _ = remove_file(log_path).await; // remove the file if it exists
+    let mut file = File::create(log_path).await.unwrap();
+
+    // the events are added to the batch by concurrent callbacks
+    let config = WatcherConfig::new().with_delivery(Delivery::Concurrent);
+    let mut log_watcher = LogWatcher::with_config(config);
+
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let running = Arc::new(AtomicUsize::new(0));
+    let max_running = Arc::new(AtomicUsize::new(0));
+    let batches_clone = batches.clone();
+    let running_clone = running.clone();
+    let max_running_clone = max_running.clone();
+    log_watcher
+        .register_batch(
+            log_path,
+            3,
+            1024,
+            Duration::from_millis(300),
+            move |log_events: Vec<LogEvent>| {
+                let batches = batches_clone.clone();
+                let running = running_clone.clone();
+                let max_running = max_running_clone.clone();
+                async move {
+                    let count = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_running.fetch_max(count, Ordering::SeqCst);
+                    // the full batch is slower than the expired one
+                    if log_events.len() == 3 {
+                        sleep(Duration::from_millis(1000)).await;
+                    }
+                    let mut lines = lines_of(&log_events);
+                    lines.sort();
+                    batches.lock().await.push(lines);
+                    running.fetch_sub(1, Ordering::SeqCst);
+                }
+            },
+        )
+        .await
+        .unwrap();
+
+    // start monitoring
+    let monitor_handle = log_watcher.start(Duration::from_millis(100)).await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+
+    // a batch expires while the full one is being delivered
+    file.write_all(b"line 1\nline 2\nline 3\n").await.unwrap();
+    file.flush().await.unwrap();
+    sleep(Duration::from_millis(200)).await;
+    file.write_all(b"line 4\n").await.unwrap();
+    file.flush().await.unwrap();
+    sleep(Duration::from_millis(1500)).await;
+    monitor_handle.shutdown().await.unwrap();
+
+    assert_eq!(
+        *batches.lock().await,
+        vec![vec!["line 1", "line 2", "line 3"], vec!["line 4"]]
+    );
+    assert_eq!(max_running.load(Ordering::SeqCst), 1);
+
+    // remove test log file
+    remove_file(log_path).await.unwrap();
+}
@@ -0,0 +1,66 @@
+#![cfg(feature = "async-std")]
+
+use async_log_watch::{LogEvent, LogWatcher};
+
+use async_std::{
+    fs::{remove_file, File},
+    io::prelude::*,
+    sync::Mutex,
+    task::{self, sleep},
+};
+
+use std::sync::Arc;
+use std::time::Duration;
+
+#[async_std::test]
+async fn log_watcher_burst_test() {
+    // ready for log file
+    let log_path = "test_log_burst.txt";
+    let _ = remove_file(log_path).await; // remove the file if it exists
+    let mut file = File::create(log_path).await.unwrap();
+
+    let mut log_watcher = LogWatcher::new();
+
+    let detected_lines = Arc::new(Mutex::new(Vec::new()));
+
+    let detected_lines_clone = detected_lines.clone();
+    log_watcher
+        .register(
+            log_path,
+            move |log_event: LogEvent| {
+                let detected_lines = detected_lines_clone.clone();
+                async move {
+                    if let Some(line) = log_event.get_line() {
+                        detected_lines.lock().await.push(line.clone());
+                    }
+                }
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+    // start monitoring
+    let _monitoring_handle = task::spawn(async move {
+        log_watcher
+            .monitoring(Duration::from_millis(100))
+            .await
+            .unwrap();
+    });
+    sleep(Duration::from_millis(500)).await;
+
+    // write ten lines in a single write
+    let burst_lines: Vec<String> = (0..10).map(|i| format!("burst {}", i)).collect();
+    let mut data = burst_lines.join("\n");
+    data.push('\n');
+    file.write_all(data.as_bytes()).await.unwrap();
+    file.flush().await.unwrap();
+
+    sleep(Duration::from_millis(500)).await;
+
+    // remove test log file
+    remove_file(log_path).await.unwrap();
+
+    // every line is delivered in order
+    assert_eq!(*detected_lines.lock().await, burst_lines);
+}
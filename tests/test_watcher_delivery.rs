@@ -0,0 +1,92 @@
+#![cfg(any(feature = "async-std", feature = "tokio"))]
+
+use async_log_watch::{Delivery, LogEvent, LogWatcher, WatcherConfig};
+
+#[cfg(feature = "async-std")]
+use async_std::{
+    fs::{remove_file, File},
+    io::prelude::*,
+    sync::Mutex,
+    task::sleep,
+};
+#[cfg(feature = "tokio")]
+use tokio::{
+    fs::{remove_file, File},
+    io::AsyncWriteExt,
+    sync::Mutex,
+    time::sleep,
+};
+
+use std::sync::Arc;
+use std::time::Duration;
+
+// write the lines in separate events and return the delivered lines. the callback of every
+// other line is slow.
+async fn deliver_lines(log_path: &str, config: WatcherConfig) -> Vec<String> {
+    let _ = remove_file(log_path).await; // remove the file if it exists
+    let mut file = File::create(log_path).await.unwrap();
+
+    let mut log_watcher = LogWatcher::with_config(config);
+
+    let lines = Arc::new(Mutex::new(Vec::new()));
+    let lines_clone = lines.clone();
+    log_watcher
+        .register(
+            log_path,
+            move |log_event: LogEvent| {
+                let lines = lines_clone.clone();
+                async move {
+                    if let Some(line) = log_event.get_line() {
+                        let n: u64 = line.trim_start_matches("line ").parse().unwrap();
+                        if n.is_multiple_of(2) {
+                            sleep(Duration::from_millis(150)).await;
+                        }
+                        lines.lock().await.push(line.clone());
+                    }
+                }
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+    let monitor_handle = log_watcher.start(Duration::from_millis(100)).await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+
+    for i in 0..6 {
+        file.write_all(format!("line {}\n", i).as_bytes())
+            .await
+            .unwrap();
+        file.flush().await.unwrap();
+        sleep(Duration::from_millis(50)).await;
+    }
+    sleep(Duration::from_millis(1000)).await;
+
+    monitor_handle.shutdown().await.unwrap();
+    remove_file(log_path).await.unwrap();
+
+    let lines = lines.lock().await.clone();
+    lines
+}
+
+#[cfg_attr(feature = "async-std", async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test(flavor = "multi_thread"))]
+async fn log_watcher_ordered_delivery_test() {
+    let lines = deliver_lines("test_log_delivery_ordered.txt", WatcherConfig::new()).await;
+
+    // the callbacks of the file ran one after the other
+    let expected: Vec<String> = (0..6).map(|i| format!("line {}", i)).collect();
+    assert_eq!(lines, expected);
+}
+
+#[cfg_attr(feature = "async-std", async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test(flavor = "multi_thread"))]
+async fn log_watcher_concurrent_delivery_test() {
+    let config = WatcherConfig::new().with_delivery(Delivery::Concurrent);
+    let mut lines = deliver_lines("test_log_delivery_concurrent.txt", config).await;
+
+    // every line is delivered, in any order
+    lines.sort();
+    let expected: Vec<String> = (0..6).map(|i| format!("line {}", i)).collect();
+    assert_eq!(lines, expected);
+}
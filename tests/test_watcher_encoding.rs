@@ -0,0 +1,146 @@
+#![cfg(feature = "async-std")]
+
+use async_log_watch::{Encoding, ErrorKind, LogEvent, LogWatcher, RegisterOptions};
+
+use async_std::{
+    fs::{remove_file, File},
+    io::prelude::*,
+    sync::Mutex,
+    task::sleep,
+};
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+type LogEvents = Arc<Mutex<Vec<LogEvent>>>;
+
+async fn register(log_watcher: &mut LogWatcher, path: &Path, encoding: Encoding) -> LogEvents {
+    let log_events = Arc::new(Mutex::new(Vec::new()));
+    let log_events_clone = log_events.clone();
+    log_watcher
+        .register_with_options(
+            path,
+            move |log_event: LogEvent| {
+                let log_events = log_events_clone.clone();
+                async move {
+                    log_events.lock().await.push(log_event);
+                }
+            },
+            RegisterOptions::new().with_encoding(encoding),
+        )
+        .await
+        .unwrap();
+    log_events
+}
+
+#[async_std::test]
+async fn log_watcher_encoding_test() {
+    // ready for log file
+    let log_path = Path::new("test_log_encoding.txt");
+    let _ = remove_file(log_path).await; // remove the file if it exists
+    let mut file = File::create(log_path).await.unwrap();
+
+    let mut log_watcher = LogWatcher::new();
+    let strict = register(&mut log_watcher, log_path, Encoding::Utf8).await;
+    let lossy = register(&mut log_watcher, log_path, Encoding::Utf8Lossy).await;
+    let raw = register(&mut log_watcher, log_path, Encoding::Raw).await;
+
+    let monitor_handle = log_watcher.start(Duration::from_millis(100)).await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+
+    file.write_all(b"caf\xe9\r\nnext\n").await.unwrap();
+    file.flush().await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+
+    monitor_handle.shutdown().await.unwrap();
+
+    // the invalid line is notified with its bytes, and the reading goes on
+    let strict = strict.lock().await;
+    assert_eq!(strict.len(), 2);
+    assert!(strict[0].get_line().is_none());
+    assert_eq!(strict[0].bytes(), Some(&b"caf\xe9"[..]));
+    assert!(matches!(
+        strict[0].get_log_error().unwrap().kind,
+        ErrorKind::InvalidUtf8Error(_)
+    ));
+    assert_eq!(strict[1].get_line().unwrap(), "next");
+
+    let lossy = lossy.lock().await;
+    assert_eq!(lossy[0].get_line().unwrap(), "caf\u{fffd}");
+    assert_eq!(lossy[1].get_line().unwrap(), "next");
+
+    let raw = raw.lock().await;
+    assert!(raw[0].get_line().is_none());
+    assert!(raw[0].get_log_error().is_none());
+    assert_eq!(raw[0].bytes(), Some(&b"caf\xe9"[..]));
+    assert_eq!(raw[1].bytes(), Some(&b"next"[..]));
+
+    // remove test log file
+    remove_file(log_path).await.unwrap();
+}
+
+#[cfg(feature = "encoding")]
+#[async_std::test]
+async fn log_watcher_utf16_test() {
+    // ready for log file
+    let log_path = Path::new("test_log_utf16.txt");
+    let _ = remove_file(log_path).await; // remove the file if it exists
+    let mut file = File::create(log_path).await.unwrap();
+
+    let mut log_watcher = LogWatcher::new();
+    let log_events = register(&mut log_watcher, log_path, Encoding::Utf16Le).await;
+
+    let monitor_handle = log_watcher.start(Duration::from_millis(100)).await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+
+    // "\u{a0a}" contains a '\n' byte which isn't a newline
+    let text = "\u{feff}first \u{a0a}\n日本語\r\npartial";
+    let bytes: Vec<u8> = text.encode_utf16().flat_map(u16::to_le_bytes).collect();
+    file.write_all(&bytes).await.unwrap();
+    file.flush().await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+
+    monitor_handle.shutdown().await.unwrap();
+
+    let lines: Vec<String> = (log_events.lock().await.iter())
+        .map(|log_event| log_event.get_line().unwrap().clone())
+        .collect();
+    assert_eq!(lines, vec!["first \u{a0a}", "日本語"]);
+
+    // remove test log file
+    remove_file(log_path).await.unwrap();
+}
+
+// a file whose path isn't valid UTF-8
+#[cfg(unix)]
+#[async_std::test]
+async fn log_watcher_non_utf8_path_test() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    // ready for log file
+    let log_path = Path::new(OsStr::from_bytes(b"test_log_\xff.txt"));
+    let _ = remove_file(log_path).await; // remove the file if it exists
+    let mut file = File::create(log_path).await.unwrap();
+
+    let mut log_watcher = LogWatcher::new();
+    let log_events = register(&mut log_watcher, log_path, Encoding::Utf8).await;
+
+    let monitor_handle = log_watcher.start(Duration::from_millis(100)).await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+
+    file.write_all(b"line 1\n").await.unwrap();
+    file.flush().await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+
+    monitor_handle.shutdown().await.unwrap();
+
+    let log_events = log_events.lock().await;
+    assert_eq!(log_events.len(), 1);
+    assert_eq!(log_events[0].get_line().unwrap(), "line 1");
+    assert_eq!(log_events[0].file_path().file_name(), log_path.file_name());
+
+    // remove test log file
+    remove_file(log_path).await.unwrap();
+}
@@ -0,0 +1,156 @@
+#![cfg(feature = "async-std")]
+
+use async_log_watch::{Filter, LogEvent, LogWatcher, LogfmtParser, RegisterOptions};
+
+use async_std::{
+    fs::{remove_file, File},
+    io::prelude::*,
+    sync::Mutex,
+    task::sleep,
+};
+
+use std::sync::Arc;
+use std::time::Duration;
+
+#[async_std::test]
+async fn log_watcher_filter_test() {
+    // ready for log file
+    let log_path = "test_log_filter.txt";
+    let _ = remove_file(log_path).await; // remove the file if it exists
+    let mut file = File::create(log_path).await.unwrap();
+
+    let mut log_watcher = LogWatcher::new();
+
+    let errors = Arc::new(Mutex::new(Vec::new()));
+    let errors_clone = errors.clone();
+    log_watcher
+        .register_with_options(
+            log_path,
+            move |log_event: LogEvent| {
+                let errors = errors_clone.clone();
+                async move {
+                    errors
+                        .lock()
+                        .await
+                        .push(log_event.get_line().unwrap().clone());
+                }
+            },
+            // ERROR but not HealthCheck
+            RegisterOptions::new().with_filter(
+                Filter::contains("ERROR").and(!Filter::contains_ignore_case("healthcheck")),
+            ),
+        )
+        .await
+        .unwrap();
+
+    let server_errors = Arc::new(Mutex::new(Vec::new()));
+    let server_errors_clone = server_errors.clone();
+    log_watcher
+        .register_with_options(
+            log_path,
+            move |log_event: LogEvent| {
+                let server_errors = server_errors_clone.clone();
+                async move {
+                    server_errors
+                        .lock()
+                        .await
+                        .push(log_event.get_line().unwrap().clone());
+                }
+            },
+            RegisterOptions::new()
+                .with_parser(LogfmtParser)
+                .with_filter(Filter::field("status", |status| {
+                    status.as_i64().is_some_and(|status| status >= 500)
+                })),
+        )
+        .await
+        .unwrap();
+
+    let monitor_handle = log_watcher.start(Duration::from_millis(100)).await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+
+    file.write_all(
+        b"level=ERROR msg=timeout status=504\n\
+          level=ERROR msg=HealthCheck status=503\n\
+          level=INFO msg=ok status=200\n",
+    )
+    .await
+    .unwrap();
+    file.flush().await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+
+    monitor_handle.shutdown().await.unwrap();
+
+    assert_eq!(
+        *errors.lock().await,
+        vec!["level=ERROR msg=timeout status=504"]
+    );
+    assert_eq!(
+        *server_errors.lock().await,
+        vec![
+            "level=ERROR msg=timeout status=504",
+            "level=ERROR msg=HealthCheck status=503",
+        ]
+    );
+
+    // remove test log file
+    remove_file(log_path).await.unwrap();
+}
+
+#[async_std::test]
+async fn log_watcher_filter_matches_test() {
+    // ready for log file
+    let log_path = "test_log_filter_matches.txt";
+    let _ = remove_file(log_path).await; // remove the file if it exists
+    let mut file = File::create(log_path).await.unwrap();
+
+    let mut log_watcher = LogWatcher::new();
+
+    let log_events = Arc::new(Mutex::new(Vec::new()));
+    let log_events_clone = log_events.clone();
+    log_watcher
+        .register_with_options(
+            log_path,
+            move |log_event: LogEvent| {
+                let log_events = log_events_clone.clone();
+                async move {
+                    log_events.lock().await.push(log_event);
+                }
+            },
+            RegisterOptions::new()
+                .with_patterns(vec!["^ERROR", r"status=(?P<status>\d+)"])
+                .with_filter(
+                    Filter::regex(r"id=(?P<request_id>\w+)")
+                        .unwrap()
+                        .named("request"),
+                ),
+        )
+        .await
+        .unwrap();
+
+    let monitor_handle = log_watcher.start(Duration::from_millis(100)).await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+
+    file.write_all(b"ERROR id=a1\nINFO id=b2 status=200\nINFO started\n")
+        .await
+        .unwrap();
+    file.flush().await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+
+    monitor_handle.shutdown().await.unwrap();
+
+    let log_events = log_events.lock().await;
+    assert_eq!(log_events.len(), 2);
+
+    assert_eq!(log_events[0].matched_patterns(), &[0, 2]);
+    assert_eq!(log_events[0].matched_names(), &["request"]);
+    assert_eq!(log_events[0].capture("request_id"), Some("a1"));
+    assert_eq!(log_events[0].capture("status"), None);
+
+    assert_eq!(log_events[1].matched_patterns(), &[1, 2]);
+    assert_eq!(log_events[1].capture("request_id"), Some("b2"));
+    assert_eq!(log_events[1].capture("status"), Some("200"));
+
+    // remove test log file
+    remove_file(log_path).await.unwrap();
+}
@@ -0,0 +1,179 @@
+#![cfg(feature = "async-std")]
+
+use async_log_watch::{Encoding, Framing, LogEvent, LogWatcher, RegisterOptions, StartPosition};
+
+use async_std::{
+    fs::{remove_file, File},
+    io::prelude::*,
+    sync::Mutex,
+    task::sleep,
+};
+
+use std::sync::Arc;
+use std::time::Duration;
+
+type Records = Arc<Mutex<Vec<Vec<u8>>>>;
+
+async fn register(log_watcher: &mut LogWatcher, path: &str, options: RegisterOptions) -> Records {
+    let records = Arc::new(Mutex::new(Vec::new()));
+    let records_clone = records.clone();
+    log_watcher
+        .register_with_options(
+            path,
+            move |log_event: LogEvent| {
+                let records = records_clone.clone();
+                async move {
+                    if let Some(bytes) = log_event.bytes() {
+                        records.lock().await.push(bytes.to_vec());
+                    }
+                }
+            },
+            options.with_encoding(Encoding::Raw),
+        )
+        .await
+        .unwrap();
+    records
+}
+
+async fn write(file: &mut File, bytes: &[u8]) {
+    file.write_all(bytes).await.unwrap();
+    file.flush().await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+}
+
+#[async_std::test]
+async fn log_watcher_framing_test() {
+    // ready for log files
+    let delimited_path = "test_log_framing_delimited.txt";
+    let binary_path = "test_log_framing_binary.txt";
+    let _ = remove_file(delimited_path).await; // remove the files if they exist
+    let _ = remove_file(binary_path).await;
+    let mut delimited_file = File::create(delimited_path).await.unwrap();
+    let mut binary_file = File::create(binary_path).await.unwrap();
+
+    // a record written before the registration
+    delimited_file.write_all(b"old\0").await.unwrap();
+    delimited_file.flush().await.unwrap();
+
+    let mut log_watcher = LogWatcher::new();
+    let options = RegisterOptions::new();
+    let nul = register(
+        &mut log_watcher,
+        delimited_path,
+        (options.clone())
+            .with_framing(Framing::delimiter(b"\0").unwrap())
+            .with_start_position(StartPosition::LastLines(1)),
+    )
+    .await;
+    let crlf = register(
+        &mut log_watcher,
+        delimited_path,
+        (options.clone()).with_framing(Framing::delimiter(b"\r\n").unwrap()),
+    )
+    .await;
+    let regex = register(
+        &mut log_watcher,
+        delimited_path,
+        (options.clone()).with_framing(Framing::regex(r"\n-{3,}\n").unwrap()),
+    )
+    .await;
+    // the invalid framings are errors
+    assert!(Framing::delimiter(b"").is_err());
+    assert!(Framing::fixed_size(0).is_err());
+
+    let fixed = register(
+        &mut log_watcher,
+        binary_path,
+        (options.clone()).with_framing(Framing::fixed_size(4).unwrap()),
+    )
+    .await;
+    let prefixed = register(
+        &mut log_watcher,
+        binary_path,
+        (options.clone()).with_framing(Framing::length_prefixed()),
+    )
+    .await;
+
+    let monitor_handle = log_watcher.start(Duration::from_millis(100)).await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+
+    // a partial record is delivered once it is complete
+    write(&mut delimited_file, b"a\nb\r\n\0x\n----").await;
+    write(&mut delimited_file, b"\ny\0\r\n").await;
+    write(&mut binary_file, b"\0\0\0\x06abcdef\0\0").await;
+    write(&mut binary_file, b"\0\x01g").await;
+
+    monitor_handle.shutdown().await.unwrap();
+
+    assert_eq!(
+        *nul.lock().await,
+        vec![
+            b"old".to_vec(),
+            b"a\nb\r\n".to_vec(),
+            b"x\n----\ny".to_vec()
+        ]
+    );
+    // the bytes written before the registration are not a complete record yet
+    assert_eq!(
+        *crlf.lock().await,
+        vec![b"old\0a\nb".to_vec(), b"\0x\n----\ny\0".to_vec()]
+    );
+    assert_eq!(*regex.lock().await, vec![b"old\0a\nb\r\n\0x".to_vec()]);
+    assert_eq!(
+        *fixed.lock().await,
+        vec![b"\0\0\0\x06".to_vec(), b"abcd".to_vec(), b"ef\0\0".to_vec()]
+    );
+    assert_eq!(
+        *prefixed.lock().await,
+        vec![b"abcdef".to_vec(), b"g".to_vec()]
+    );
+
+    // remove test log files
+    remove_file(delimited_path).await.unwrap();
+    remove_file(binary_path).await.unwrap();
+}
+
+#[async_std::test]
+async fn log_watcher_framing_max_frame_size_test() {
+    // ready for log file
+    let log_path = "test_log_framing_max_frame_size.txt";
+    let _ = remove_file(log_path).await; // remove the file if it exists
+    let mut file = File::create(log_path).await.unwrap();
+
+    let mut log_watcher = LogWatcher::new();
+
+    // a text file read as length-prefixed records: "line" is a length of about 1.8 GB
+    let errors = Arc::new(Mutex::new(Vec::new()));
+    let errors_clone = errors.clone();
+    log_watcher
+        .register_with_options(
+            log_path,
+            move |log_event: LogEvent| {
+                let errors = errors_clone.clone();
+                async move {
+                    if let Some(log_error) = log_event.get_log_error() {
+                        errors.lock().await.push(log_error.to_string());
+                    }
+                }
+            },
+            RegisterOptions::new().with_framing(Framing::length_prefixed()),
+        )
+        .await
+        .unwrap();
+
+    let monitor_handle = log_watcher.start(Duration::from_millis(100)).await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+
+    // the length is rejected instead of allocating the record
+    write(&mut file, b"line one\n").await;
+    // and the error isn't notified again by the next writes
+    write(&mut file, b"line two\n").await;
+    monitor_handle.shutdown().await.unwrap();
+
+    let errors = errors.lock().await;
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].contains("more than the maximum"));
+
+    // remove test log file
+    remove_file(log_path).await.unwrap();
+}
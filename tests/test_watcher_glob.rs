@@ -0,0 +1,110 @@
+#![cfg(feature = "async-std")]
+
+use async_log_watch::{LogEvent, LogWatcher};
+
+use async_std::{
+    fs::{self, remove_file, File, OpenOptions},
+    io::prelude::*,
+    sync::Mutex,
+    task::{self, sleep},
+};
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+async fn append(file_path: &str, data: &str) {
+    let mut file = OpenOptions::new()
+        .append(true)
+        .open(file_path)
+        .await
+        .unwrap();
+    file.write_all(data.as_bytes()).await.unwrap();
+    file.flush().await.unwrap();
+}
+
+#[async_std::test]
+async fn log_watcher_glob_test() {
+    // ready for log directory
+    let log_dir = "test_glob_logs";
+    let _ = fs::remove_dir_all(log_dir).await; // remove the directory if it exists
+    fs::create_dir(log_dir).await.unwrap();
+    File::create("test_glob_logs/a.log").await.unwrap();
+
+    let mut log_watcher = LogWatcher::new();
+
+    let detected_events = Arc::new(Mutex::new(Vec::new()));
+
+    let detected_events_clone = detected_events.clone();
+    log_watcher
+        .register_glob(
+            "test_glob_logs/*.log",
+            move |log_event: LogEvent| {
+                let detected_events = detected_events_clone.clone();
+                async move {
+                    let file_name = Path::new(log_event.file_path())
+                        .file_name()
+                        .unwrap()
+                        .to_string_lossy()
+                        .into_owned();
+                    if let Some(line) = log_event.get_line() {
+                        detected_events
+                            .lock()
+                            .await
+                            .push(format!("{}: {}", file_name, line));
+                    } else if let Some(rotation) = log_event.get_rotation() {
+                        detected_events
+                            .lock()
+                            .await
+                            .push(format!("{}: {:?}", file_name, rotation));
+                    }
+                }
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+    // start monitoring
+    let _monitoring_handle = task::spawn(async move {
+        log_watcher
+            .monitoring(Duration::from_millis(100))
+            .await
+            .unwrap();
+    });
+    sleep(Duration::from_millis(500)).await;
+
+    // an existing file
+    append("test_glob_logs/a.log", "line a\n").await;
+    sleep(Duration::from_millis(300)).await;
+
+    // a new matching file is monitored from the beginning
+    File::create("test_glob_logs/b.log").await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+    append("test_glob_logs/b.log", "line b\n").await;
+    sleep(Duration::from_millis(300)).await;
+
+    // a file not matching the pattern
+    File::create("test_glob_logs/c.txt").await.unwrap();
+    append("test_glob_logs/c.txt", "line c\n").await;
+    sleep(Duration::from_millis(300)).await;
+
+    // a removed file stops being monitored
+    remove_file("test_glob_logs/b.log").await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+
+    let detected_events = detected_events.lock().await.clone();
+
+    // remove test log directory
+    fs::remove_dir_all(log_dir).await.unwrap();
+
+    assert_eq!(
+        detected_events,
+        vec![
+            "a.log: line a",
+            "b.log: Created",
+            "b.log: line b",
+            "b.log: Removed"
+        ]
+    );
+}
@@ -0,0 +1,131 @@
+#![cfg(any(feature = "async-std", feature = "tokio"))]
+
+use async_log_watch::{Error, LogEvent, LogWatcher, Multiline};
+
+#[cfg(feature = "async-std")]
+use async_std::{
+    fs::{create_dir_all, remove_dir_all, File},
+    io::prelude::*,
+    sync::Mutex,
+    task::sleep,
+};
+#[cfg(feature = "tokio")]
+use tokio::{
+    fs::{create_dir_all, remove_dir_all, File},
+    io::AsyncWriteExt,
+    sync::Mutex,
+    time::sleep,
+};
+
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg_attr(feature = "async-std", async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test(flavor = "multi_thread"))]
+async fn log_watcher_handle_test() {
+    // the directory doesn't exist when the monitoring starts
+    let log_dir = "test_log_handle";
+    let _ = remove_dir_all(log_dir).await;
+
+    let log_watcher = LogWatcher::new();
+    let handle = log_watcher.handle();
+
+    // start monitoring without any file
+    let monitor_handle = log_watcher.start(Duration::from_millis(100)).await.unwrap();
+
+    create_dir_all(log_dir).await.unwrap();
+    let log_path_1 = format!("{}/app.log", log_dir);
+    let log_path_2 = format!("{}/app-new.log", log_dir);
+    let mut file_1 = File::create(&log_path_1).await.unwrap();
+    let mut file_2 = File::create(&log_path_2).await.unwrap();
+
+    // register a file while monitoring
+    let lines = Arc::new(Mutex::new(Vec::new()));
+    let lines_clone = lines.clone();
+    handle
+        .register(
+            &log_path_1,
+            move |log_event: LogEvent| {
+                let lines = lines_clone.clone();
+                async move {
+                    if let Some(line) = log_event.get_line() {
+                        lines.lock().await.push(line.clone());
+                    }
+                }
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+    file_1.write_all(b"line 1\n").await.unwrap();
+    file_1.flush().await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+    assert_eq!(*lines.lock().await, vec!["line 1"]);
+
+    // change the file path from a cloned handle
+    let cloned_handle = handle.clone();
+    cloned_handle
+        .change_file_path(&log_path_1, &log_path_2)
+        .await
+        .unwrap();
+
+    file_1.write_all(b"line 2\n").await.unwrap();
+    file_1.flush().await.unwrap();
+    file_2.write_all(b"line 3\n").await.unwrap();
+    file_2.flush().await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+    assert_eq!(*lines.lock().await, vec!["line 1", "line 3"]);
+
+    // stop monitoring the file
+    handle.stop_monitoring_file(&log_path_2).await.unwrap();
+
+    file_2.write_all(b"line 4\n").await.unwrap();
+    file_2.flush().await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+    assert_eq!(*lines.lock().await, vec!["line 1", "line 3"]);
+
+    monitor_handle.shutdown().await.unwrap();
+
+    // remove test log files
+    remove_dir_all(log_dir).await.unwrap();
+}
+
+#[cfg_attr(feature = "async-std", async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test(flavor = "multi_thread"))]
+async fn log_watcher_register_error_test() {
+    let log_watcher = LogWatcher::new();
+    let handle = log_watcher.handle();
+
+    // a misconfigured pattern is returned as an error
+    let result = handle
+        .register("test_log_error.txt", |_| async {}, Some(vec!["(unclosed"]))
+        .await;
+    assert!(matches!(result, Err(Error::InvalidPattern(_))));
+
+    let result = handle
+        .register_glob("test_log_error/[.log", |_| async {}, None)
+        .await;
+    assert!(matches!(result, Err(Error::InvalidPattern(_))));
+
+    assert!(matches!(
+        Multiline::starts_with("[unclosed"),
+        Err(Error::InvalidPattern(_))
+    ));
+
+    // a path without a file name
+    let result = handle.register("/", |_| async {}, None).await;
+    assert!(matches!(result, Err(Error::InvalidPath(_))));
+
+    // a directory which can't be watched while monitoring
+    let monitor_handle = log_watcher.start(Duration::from_millis(100)).await.unwrap();
+    let result = handle
+        .register("test_log_error_missing/test_log.txt", |_| async {}, None)
+        .await;
+    assert!(matches!(result, Err(Error::EventError(_))));
+    let result = handle
+        .register_glob("test_log_error_missing/*.log", |_| async {}, None)
+        .await;
+    assert!(matches!(result, Err(Error::EventError(_))));
+    monitor_handle.shutdown().await.unwrap();
+}
@@ -0,0 +1,98 @@
+#![cfg(feature = "async-std")]
+
+use async_log_watch::{LogEvent, LogWatcher};
+
+use async_std::{
+    fs::{remove_file, File},
+    io::prelude::*,
+    sync::Mutex,
+    task::sleep,
+};
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[async_std::test]
+async fn log_watcher_independent_files_test() {
+    // ready for log files
+    let slow_path = "test_log_independent_slow.txt";
+    let fast_path = "test_log_independent_fast.txt";
+    let _ = remove_file(slow_path).await; // remove the files if they exist
+    let _ = remove_file(fast_path).await;
+    let mut slow_file = File::create(slow_path).await.unwrap();
+    let mut fast_file = File::create(fast_path).await.unwrap();
+
+    let mut log_watcher = LogWatcher::new();
+
+    let slow_lines = Arc::new(Mutex::new(Vec::new()));
+    let slow_lines_clone = slow_lines.clone();
+    log_watcher
+        .register(
+            slow_path,
+            move |log_event: LogEvent| {
+                let slow_lines = slow_lines_clone.clone();
+                async move {
+                    // a slow callback
+                    sleep(Duration::from_millis(1500)).await;
+                    if let Some(line) = log_event.get_line() {
+                        slow_lines.lock().await.push(line.clone());
+                    }
+                }
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+    let fast_lines = Arc::new(Mutex::new(Vec::new()));
+    let fast_lines_clone = fast_lines.clone();
+    log_watcher
+        .register(
+            fast_path,
+            move |log_event: LogEvent| {
+                let fast_lines = fast_lines_clone.clone();
+                async move {
+                    if let Some(line) = log_event.get_line() {
+                        fast_lines.lock().await.push(line.clone());
+                    }
+                }
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+    // start monitoring
+    let handle = log_watcher.handle();
+    let monitor_handle = log_watcher.start(Duration::from_millis(100)).await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+
+    slow_file.write_all(b"slow 1\n").await.unwrap();
+    slow_file.flush().await.unwrap();
+    sleep(Duration::from_millis(200)).await;
+
+    // the other file progresses while the slow callback is running
+    for i in 1..=3 {
+        fast_file
+            .write_all(format!("fast {}\n", i).as_bytes())
+            .await
+            .unwrap();
+        fast_file.flush().await.unwrap();
+        sleep(Duration::from_millis(100)).await;
+    }
+    sleep(Duration::from_millis(200)).await;
+    assert_eq!(*fast_lines.lock().await, vec!["fast 1", "fast 2", "fast 3"]);
+    assert!(slow_lines.lock().await.is_empty());
+
+    // and the files can be changed without waiting for the slow callback
+    let started = Instant::now();
+    handle.stop_monitoring_file(fast_path).await.unwrap();
+    assert!(started.elapsed() < Duration::from_millis(500));
+
+    monitor_handle.shutdown().await.unwrap();
+    assert_eq!(*slow_lines.lock().await, vec!["slow 1"]);
+
+    // remove test log files
+    remove_file(slow_path).await.unwrap();
+    remove_file(fast_path).await.unwrap();
+}
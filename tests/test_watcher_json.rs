@@ -0,0 +1,81 @@
+#![cfg(all(feature = "async-std", feature = "json"))]
+
+use async_log_watch::{ErrorKind, LogEvent, LogWatcher, RegisterOptions};
+
+use async_std::{
+    fs::{remove_file, File},
+    io::prelude::*,
+    sync::Mutex,
+    task::sleep,
+};
+
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Entry {
+    level: String,
+    message: String,
+}
+
+#[async_std::test]
+async fn log_watcher_json_test() {
+    // ready for log file
+    let log_path = "test_log_json.txt";
+    let _ = remove_file(log_path).await; // remove the file if it exists
+    let mut file = File::create(log_path).await.unwrap();
+
+    let mut log_watcher = LogWatcher::new();
+
+    let log_events = Arc::new(Mutex::new(Vec::new()));
+    let log_events_clone = log_events.clone();
+    log_watcher
+        .register_with_options(
+            log_path,
+            move |log_event: LogEvent| {
+                let log_events = log_events_clone.clone();
+                async move {
+                    log_events.lock().await.push(log_event);
+                }
+            },
+            RegisterOptions::new().with_json(true),
+        )
+        .await
+        .unwrap();
+
+    let monitor_handle = log_watcher.start(Duration::from_millis(100)).await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+
+    file.write_all(b"{\"level\":\"info\",\"message\":\"started\"}\nnot json\n")
+        .await
+        .unwrap();
+    file.flush().await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+
+    monitor_handle.shutdown().await.unwrap();
+
+    let log_events = log_events.lock().await;
+    assert_eq!(log_events.len(), 2);
+
+    let json = log_events[0].json().unwrap();
+    assert_eq!(json["level"], "info");
+    assert_eq!(
+        log_events[0].parse::<Entry>().unwrap(),
+        Entry {
+            level: "info".to_owned(),
+            message: "started".to_owned(),
+        }
+    );
+
+    // the line is kept with the parse error
+    assert_eq!(log_events[1].get_line().unwrap(), "not json");
+    assert!(log_events[1].json().is_none());
+    assert!(matches!(
+        log_events[1].get_log_error().unwrap().kind,
+        ErrorKind::JsonParseError(_)
+    ));
+
+    // remove test log file
+    remove_file(log_path).await.unwrap();
+}
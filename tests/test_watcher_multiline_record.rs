@@ -0,0 +1,85 @@
+#![cfg(feature = "async-std")]
+
+use async_log_watch::{LogEvent, LogWatcher, Multiline, RegisterOptions};
+
+use async_std::{
+    fs::{remove_file, File},
+    io::prelude::*,
+    sync::Mutex,
+    task::sleep,
+};
+
+use std::sync::Arc;
+use std::time::Duration;
+
+#[async_std::test]
+async fn log_watcher_multiline_record_test() {
+    // ready for log file
+    let log_path = "test_log_multiline_record.txt";
+    let _ = remove_file(log_path).await; // remove the file if it exists
+    let mut file = File::create(log_path).await.unwrap();
+
+    let mut log_watcher = LogWatcher::new();
+
+    let records = Arc::new(Mutex::new(Vec::new()));
+    let records_clone = records.clone();
+    log_watcher
+        .register_with_options(
+            log_path,
+            move |log_event: LogEvent| {
+                let records = records_clone.clone();
+                async move {
+                    if let Some(line) = log_event.get_line() {
+                        records.lock().await.push(line.clone());
+                    }
+                }
+            },
+            RegisterOptions::new()
+                .with_patterns(vec!["ERROR"])
+                .with_multiline(
+                    Multiline::indented().with_flush_timeout(Duration::from_millis(500)),
+                ),
+        )
+        .await
+        .unwrap();
+
+    let monitor_handle = log_watcher.start(Duration::from_millis(100)).await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+
+    // a stack trace written in several parts
+    file.write_all(b"INFO starting\nERROR failed\n    at Main.run\n")
+        .await
+        .unwrap();
+    file.flush().await.unwrap();
+    sleep(Duration::from_millis(100)).await;
+    file.write_all(b"    at Main.main\nINFO retrying\n")
+        .await
+        .unwrap();
+    file.flush().await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+    assert_eq!(
+        *records.lock().await,
+        vec!["ERROR failed\n    at Main.run\n    at Main.main"]
+    );
+
+    // the last record is delivered after the flush timeout
+    file.write_all(b"ERROR timeout\n    at Main.wait\n")
+        .await
+        .unwrap();
+    file.flush().await.unwrap();
+    sleep(Duration::from_millis(200)).await;
+    assert_eq!(records.lock().await.len(), 1);
+    sleep(Duration::from_millis(700)).await;
+    assert_eq!(
+        *records.lock().await,
+        vec![
+            "ERROR failed\n    at Main.run\n    at Main.main",
+            "ERROR timeout\n    at Main.wait",
+        ]
+    );
+
+    monitor_handle.shutdown().await.unwrap();
+
+    // remove test log file
+    remove_file(log_path).await.unwrap();
+}
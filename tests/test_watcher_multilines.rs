@@ -1,3 +1,5 @@
+#![cfg(feature = "async-std")]
+
 use async_log_watch::{LogEvent, LogWatcher};
 
 use async_std::{
@@ -24,6 +26,7 @@ async fn log_watcher_test() {
     // ready for log file
     let log_path = "test_log.txt";
     let _ = remove_file(log_path).await; // remove the file if it exists
+    File::create(log_path).await.unwrap();
 
     // initialize the watcher
     let log_watcher = LogWatcher::new();
@@ -46,7 +49,8 @@ async fn log_watcher_test() {
             },
             None,
         )
-        .await;
+        .await
+        .unwrap();
 
     // start monitoring
     let _monitoring_handle = task::spawn(async move {
@@ -0,0 +1,240 @@
+#![cfg(feature = "async-std")]
+
+use async_log_watch::{
+    CheckpointKey, FileOffsetStore, LogEvent, LogWatcher, MemoryOffsetStore, OffsetStore,
+    RegisterOptions,
+};
+
+use async_std::{
+    fs::{remove_file, File, OpenOptions},
+    io::prelude::*,
+    sync::Mutex,
+    task::sleep,
+};
+
+use std::sync::Arc;
+use std::time::Duration;
+
+// monitor the file until the shutdown and return the delivered lines.
+async fn watch_once(log_path: &str, offsets_path: &str, lines_to_write: &[&str]) -> Vec<String> {
+    let mut log_watcher = LogWatcher::new();
+    let offset_store = Arc::new(FileOffsetStore::open(offsets_path).unwrap());
+
+    let lines = Arc::new(Mutex::new(Vec::new()));
+    let lines_clone = lines.clone();
+    log_watcher
+        .register_with_options(
+            log_path,
+            move |log_event: LogEvent| {
+                let lines = lines_clone.clone();
+                async move {
+                    if let Some(line) = log_event.get_line() {
+                        lines.lock().await.push(line.clone());
+                    }
+                }
+            },
+            RegisterOptions::new().with_offset_store(offset_store, "lines"),
+        )
+        .await
+        .unwrap();
+
+    let monitor_handle = log_watcher.start(Duration::from_millis(100)).await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+
+    let mut file = OpenOptions::new()
+        .append(true)
+        .open(log_path)
+        .await
+        .unwrap();
+    for line in lines_to_write {
+        file.write_all(format!("{}\n", line).as_bytes())
+            .await
+            .unwrap();
+        file.flush().await.unwrap();
+        sleep(Duration::from_millis(300)).await;
+    }
+
+    monitor_handle.shutdown().await.unwrap();
+    let lines = lines.lock().await.clone();
+    lines
+}
+
+#[async_std::test]
+async fn log_watcher_offset_test() {
+    // ready for log file
+    let log_path = "test_log_offset.txt";
+    let offsets_path = "test_log_offset.json";
+    let _ = remove_file(log_path).await; // remove the file if it exists
+    let _ = remove_file(offsets_path).await;
+    File::create(log_path).await.unwrap();
+
+    // without a saved offset, the monitoring starts from the end
+    let lines = watch_once(log_path, offsets_path, &["line 1", "line 2"]).await;
+    assert_eq!(lines, vec!["line 1", "line 2"]);
+
+    // written while the watcher is stopped
+    let mut file = OpenOptions::new()
+        .append(true)
+        .open(log_path)
+        .await
+        .unwrap();
+    file.write_all(b"line 3\nline 4\n").await.unwrap();
+    file.flush().await.unwrap();
+
+    // resume from the saved offset
+    let lines = watch_once(log_path, offsets_path, &["line 5"]).await;
+    assert_eq!(lines, vec!["line 3", "line 4", "line 5"]);
+
+    // a replaced file is read from the beginning
+    remove_file(log_path).await.unwrap();
+    let mut file = File::create(log_path).await.unwrap();
+    file.write_all(b"new 1\n").await.unwrap();
+    file.flush().await.unwrap();
+
+    let lines = watch_once(log_path, offsets_path, &["new 2"]).await;
+    assert_eq!(lines, vec!["new 1", "new 2"]);
+
+    // remove test files
+    remove_file(log_path).await.unwrap();
+    remove_file(offsets_path).await.unwrap();
+}
+
+#[async_std::test]
+async fn log_watcher_offset_shared_store_test() {
+    // ready for log file
+    let log_path = "test_log_offset_shared.txt";
+    let _ = remove_file(log_path).await; // remove the file if it exists
+    let mut file = File::create(log_path).await.unwrap();
+    let absolute_path = std::env::current_dir().unwrap().join(log_path);
+
+    // two registrations of the file sharing a store
+    let mut log_watcher = LogWatcher::new();
+    let offset_store = Arc::new(MemoryOffsetStore::new());
+    let options = |name| RegisterOptions::new().with_offset_store(offset_store.clone(), name);
+    log_watcher
+        .register_with_options(log_path, |_: LogEvent| async {}, options("lines"))
+        .await
+        .unwrap();
+    log_watcher
+        .register_batch_with_options(
+            log_path,
+            10,
+            1024,
+            Duration::from_secs(10),
+            |_: Vec<LogEvent>| async {},
+            options("batches"),
+        )
+        .await
+        .unwrap();
+
+    let monitor_handle = log_watcher.start(Duration::from_millis(100)).await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+
+    file.write_all(b"line 1\nline 2\n").await.unwrap();
+    file.flush().await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+
+    // each registration has its own offset, the lines waiting in the batch aren't saved
+    let key = |name: &str| CheckpointKey {
+        path: absolute_path.clone(),
+        name: name.to_owned(),
+    };
+    let checkpoint = offset_store.load(&key("lines")).unwrap().unwrap();
+    assert_eq!(checkpoint.offset, 14);
+    assert_eq!(offset_store.load(&key("batches")).unwrap(), None);
+
+    monitor_handle.shutdown().await.unwrap();
+
+    // remove test log file
+    remove_file(log_path).await.unwrap();
+}
+
+// register the file under each name in order, write the lines and return the lines delivered
+// to each registration.
+async fn watch_names(
+    log_path: &str,
+    offset_store: &Arc<MemoryOffsetStore>,
+    names: &[&str],
+    lines_to_write: &[&str],
+) -> Vec<Vec<String>> {
+    let mut log_watcher = LogWatcher::new();
+    let mut delivered = Vec::new();
+    for name in names {
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let lines_clone = lines.clone();
+        log_watcher
+            .register_with_options(
+                log_path,
+                move |log_event: LogEvent| {
+                    let lines = lines_clone.clone();
+                    async move {
+                        if let Some(line) = log_event.get_line() {
+                            lines.lock().await.push(line.clone());
+                        }
+                    }
+                },
+                RegisterOptions::new().with_offset_store(offset_store.clone(), name),
+            )
+            .await
+            .unwrap();
+        delivered.push(lines);
+    }
+
+    let monitor_handle = log_watcher.start(Duration::from_millis(100)).await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+    let mut file = OpenOptions::new()
+        .append(true)
+        .open(log_path)
+        .await
+        .unwrap();
+    for line in lines_to_write {
+        file.write_all(format!("{}\n", line).as_bytes())
+            .await
+            .unwrap();
+        file.flush().await.unwrap();
+        sleep(Duration::from_millis(300)).await;
+    }
+    monitor_handle.shutdown().await.unwrap();
+
+    let mut lines = Vec::new();
+    for delivered in delivered {
+        lines.push(delivered.lock().await.clone());
+    }
+    lines
+}
+
+#[async_std::test]
+async fn log_watcher_offset_reordered_test() {
+    // ready for log file
+    let log_path = "test_log_offset_reordered.txt";
+    let _ = remove_file(log_path).await; // remove the file if it exists
+    File::create(log_path).await.unwrap();
+
+    let offset_store = Arc::new(MemoryOffsetStore::new());
+    let lines = watch_names(log_path, &offset_store, &["first", "second"], &["line 1"]).await;
+    assert_eq!(lines, vec![vec!["line 1"], vec!["line 1"]]);
+
+    // written while the watcher is stopped
+    let mut file = OpenOptions::new()
+        .append(true)
+        .open(log_path)
+        .await
+        .unwrap();
+    file.write_all(b"line 2\n").await.unwrap();
+    file.flush().await.unwrap();
+
+    // the registrations are resumed by their names, in another order and after a new one
+    let names = ["new", "second", "first"];
+    let lines = watch_names(log_path, &offset_store, &names, &["line 3"]).await;
+    assert_eq!(
+        lines,
+        vec![
+            vec!["line 3"],
+            vec!["line 2", "line 3"],
+            vec!["line 2", "line 3"]
+        ]
+    );
+
+    // remove test log file
+    remove_file(log_path).await.unwrap();
+}
@@ -0,0 +1,73 @@
+#![cfg(feature = "async-std")]
+
+use async_log_watch::{ErrorKind, LogEvent, LogWatcher, LogfmtParser, RegisterOptions};
+
+use async_std::{
+    fs::{remove_file, File},
+    io::prelude::*,
+    sync::Mutex,
+    task::sleep,
+};
+
+use std::sync::Arc;
+use std::time::Duration;
+
+#[async_std::test]
+async fn log_watcher_parser_test() {
+    // ready for log file
+    let log_path = "test_log_parser.txt";
+    let _ = remove_file(log_path).await; // remove the file if it exists
+    let mut file = File::create(log_path).await.unwrap();
+
+    let mut log_watcher = LogWatcher::new();
+
+    let log_events = Arc::new(Mutex::new(Vec::new()));
+    let log_events_clone = log_events.clone();
+    log_watcher
+        .register_with_options(
+            log_path,
+            move |log_event: LogEvent| {
+                let log_events = log_events_clone.clone();
+                async move {
+                    log_events.lock().await.push(log_event);
+                }
+            },
+            RegisterOptions::new().with_parser(LogfmtParser),
+        )
+        .await
+        .unwrap();
+
+    let monitor_handle = log_watcher.start(Duration::from_millis(100)).await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+
+    file.write_all(b"level=error msg=\"request failed\" status=503\nmsg=\"unclosed\n")
+        .await
+        .unwrap();
+    file.flush().await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+
+    monitor_handle.shutdown().await.unwrap();
+
+    let log_events = log_events.lock().await;
+    assert_eq!(log_events.len(), 2);
+
+    let log_event = &log_events[0];
+    assert_eq!(log_event.field("level").unwrap().as_str(), Some("error"));
+    assert_eq!(
+        log_event.field("msg").unwrap().as_str(),
+        Some("request failed")
+    );
+    assert_eq!(log_event.field("status").unwrap().as_i64(), Some(503));
+
+    // the line is kept with the parse error
+    let log_event = &log_events[1];
+    assert_eq!(log_event.get_line().unwrap(), "msg=\"unclosed");
+    assert!(log_event.fields().is_none());
+    assert!(matches!(
+        log_event.get_log_error().unwrap().kind,
+        ErrorKind::LineParseError(_)
+    ));
+
+    // remove test log file
+    remove_file(log_path).await.unwrap();
+}
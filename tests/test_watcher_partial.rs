@@ -0,0 +1,78 @@
+#![cfg(feature = "async-std")]
+
+use async_log_watch::{LogEvent, LogWatcher, RegisterOptions};
+
+use async_std::{
+    fs::{remove_file, File},
+    io::prelude::*,
+    sync::Mutex,
+    task::sleep,
+};
+
+use std::sync::Arc;
+use std::time::Duration;
+
+#[async_std::test]
+async fn log_watcher_partial_test() {
+    // ready for log file
+    let log_path = "test_log_partial.txt";
+    let _ = remove_file(log_path).await; // remove the file if it exists
+    let mut file = File::create(log_path).await.unwrap();
+
+    let mut log_watcher = LogWatcher::new();
+
+    let log_events = Arc::new(Mutex::new(Vec::new()));
+    let log_events_clone = log_events.clone();
+    log_watcher
+        .register_with_options(
+            log_path,
+            move |log_event: LogEvent| {
+                let log_events = log_events_clone.clone();
+                async move {
+                    log_events.lock().await.push(log_event);
+                }
+            },
+            RegisterOptions::new().with_partial_flush(Duration::from_millis(300)),
+        )
+        .await
+        .unwrap();
+
+    let monitor_handle = log_watcher.start(Duration::from_millis(100)).await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+
+    // the last message of a crashed process, the '\r' waits for the rest of the line
+    file.write_all(b"first\ncrash msg\r").await.unwrap();
+    file.flush().await.unwrap();
+    sleep(Duration::from_millis(150)).await;
+    assert_eq!(log_events.lock().await.len(), 1);
+    sleep(Duration::from_millis(600)).await;
+
+    // the rest of the line isn't delivered again with the partial line
+    file.write_all(b"\nnext\n").await.unwrap();
+    file.flush().await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+
+    monitor_handle.shutdown().await.unwrap();
+
+    let log_events = log_events.lock().await;
+    let lines: Vec<_> = (log_events.iter())
+        .map(|log_event| {
+            (
+                log_event.get_line().unwrap().as_str(),
+                log_event.is_partial(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        lines,
+        vec![
+            ("first", false),
+            ("crash msg", true),
+            ("", false),
+            ("next", false)
+        ]
+    );
+
+    // remove test log file
+    remove_file(log_path).await.unwrap();
+}
@@ -0,0 +1,113 @@
+#![cfg(any(feature = "async-std", feature = "tokio"))]
+
+use async_log_watch::{LogEvent, LogWatcher};
+
+#[cfg(feature = "async-std")]
+use async_std::{
+    fs::{self, remove_file, File, OpenOptions},
+    io::prelude::*,
+    sync::Mutex,
+    task::{sleep, spawn},
+};
+#[cfg(feature = "tokio")]
+use tokio::{
+    fs::{self, remove_file, File, OpenOptions},
+    io::AsyncWriteExt,
+    spawn,
+    sync::Mutex,
+    time::sleep,
+};
+
+use std::sync::Arc;
+use std::time::Duration;
+
+async fn append(file_path: &str, data: &str) {
+    let mut file = OpenOptions::new()
+        .append(true)
+        .open(file_path)
+        .await
+        .unwrap();
+    file.write_all(data.as_bytes()).await.unwrap();
+    file.flush().await.unwrap();
+}
+
+#[cfg_attr(feature = "async-std", async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test(flavor = "multi_thread"))]
+async fn log_watcher_rotation_test() {
+    // ready for log file
+    let log_path = "test_log_rotation.txt";
+    let rotated_path = "test_log_rotation.txt.1";
+    let _ = remove_file(log_path).await; // remove the files if they exist
+    let _ = remove_file(rotated_path).await;
+    File::create(log_path).await.unwrap();
+
+    let mut log_watcher = LogWatcher::new();
+
+    let detected_events = Arc::new(Mutex::new(Vec::new()));
+
+    let detected_events_clone = detected_events.clone();
+    log_watcher
+        .register(
+            log_path,
+            move |log_event: LogEvent| {
+                let detected_events = detected_events_clone.clone();
+                async move {
+                    if let Some(line) = log_event.get_line() {
+                        detected_events.lock().await.push(line.clone());
+                    } else if let Some(rotation) = log_event.get_rotation() {
+                        detected_events.lock().await.push(format!("{:?}", rotation));
+                    }
+                }
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+    // start monitoring
+    let _monitoring_handle = spawn(async move {
+        log_watcher
+            .monitoring(Duration::from_millis(100))
+            .await
+            .unwrap();
+    });
+    sleep(Duration::from_millis(500)).await;
+
+    append(log_path, "line 1\n").await;
+    sleep(Duration::from_millis(300)).await;
+
+    // copytruncate
+    fs::copy(log_path, rotated_path).await.unwrap();
+    File::create(log_path).await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+    append(log_path, "line 2\n").await;
+    sleep(Duration::from_millis(300)).await;
+
+    // rename and create, the line written just before the rename is read from the old file
+    append(log_path, "line 3\n").await;
+    fs::rename(log_path, rotated_path).await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+    File::create(log_path).await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+    append(log_path, "line 4\n").await;
+    sleep(Duration::from_millis(300)).await;
+
+    let detected_events = detected_events.lock().await.clone();
+
+    // remove test log files
+    remove_file(log_path).await.unwrap();
+    remove_file(rotated_path).await.unwrap();
+
+    assert_eq!(
+        detected_events,
+        vec![
+            "line 1",
+            "Truncated",
+            "line 2",
+            "line 3",
+            "Removed",
+            "Created",
+            "line 4"
+        ]
+    );
+}
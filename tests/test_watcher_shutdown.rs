@@ -0,0 +1,80 @@
+#![cfg(any(feature = "async-std", feature = "tokio"))]
+
+use async_log_watch::{LogEvent, LogWatcher};
+
+#[cfg(feature = "async-std")]
+use async_std::{
+    fs::{remove_file, File},
+    io::prelude::*,
+    task::sleep,
+};
+#[cfg(feature = "tokio")]
+use tokio::{
+    fs::{remove_file, File},
+    io::AsyncWriteExt,
+    time::sleep,
+};
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg_attr(feature = "async-std", async_std::test)]
+#[cfg_attr(feature = "tokio", tokio::test(flavor = "multi_thread"))]
+async fn log_watcher_shutdown_test() {
+    // ready for log file
+    let log_path = "test_log_shutdown.txt";
+    let _ = remove_file(log_path).await; // remove the file if it exists
+    let mut file = File::create(log_path).await.unwrap();
+
+    let mut log_watcher = LogWatcher::new();
+
+    let started_count = Arc::new(AtomicUsize::new(0));
+    let finished_count = Arc::new(AtomicUsize::new(0));
+
+    let started_count_clone = started_count.clone();
+    let finished_count_clone = finished_count.clone();
+    log_watcher
+        .register(
+            log_path,
+            move |_: LogEvent| {
+                let started_count = started_count_clone.clone();
+                let finished_count = finished_count_clone.clone();
+                async move {
+                    started_count.fetch_add(1, Ordering::SeqCst);
+                    // a slow callback
+                    sleep(Duration::from_millis(500)).await;
+                    finished_count.fetch_add(1, Ordering::SeqCst);
+                }
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+    // start monitoring
+    let monitor_handle = log_watcher.start(Duration::from_millis(100)).await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+
+    file.write_all(b"line 1\n").await.unwrap();
+    file.flush().await.unwrap();
+    sleep(Duration::from_millis(200)).await;
+    assert_eq!(started_count.load(Ordering::SeqCst), 1);
+
+    // shutdown waits for the running callback
+    monitor_handle.shutdown().await.unwrap();
+    assert_eq!(finished_count.load(Ordering::SeqCst), 1);
+
+    // no more events after the shutdown
+    file.write_all(b"line 2\n").await.unwrap();
+    file.flush().await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+    assert_eq!(started_count.load(Ordering::SeqCst), 1);
+
+    // the monitoring can be started again
+    let monitor_handle = log_watcher.start(Duration::from_millis(100)).await.unwrap();
+    monitor_handle.shutdown().await.unwrap();
+
+    // remove test log file
+    remove_file(log_path).await.unwrap();
+}
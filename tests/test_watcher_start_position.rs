@@ -0,0 +1,83 @@
+#![cfg(feature = "async-std")]
+
+use async_log_watch::{LogEvent, LogWatcher, RegisterOptions, StartPosition};
+
+use async_std::{
+    fs::{remove_file, File},
+    io::prelude::*,
+    sync::Mutex,
+    task::sleep,
+};
+
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+
+#[async_std::test]
+async fn log_watcher_start_position_test() {
+    // ready for log file
+    let log_path = "test_log_start_position.txt";
+    let _ = remove_file(log_path).await; // remove the file if it exists
+    let mut file = File::create(log_path).await.unwrap();
+    file.write_all(
+        b"2023-05-01T10:00:00Z line 1\n2023-05-01T11:00:00Z line 2\n2023-05-01T12:00:00Z line 3\n",
+    )
+    .await
+    .unwrap();
+    file.flush().await.unwrap();
+
+    let mut log_watcher = LogWatcher::new();
+
+    let start_positions = vec![
+        StartPosition::Beginning,
+        StartPosition::End,
+        StartPosition::LastLines(2),
+        StartPosition::Offset(28),
+        StartPosition::Since(UNIX_EPOCH + Duration::from_secs(1682938800)), // 11:00
+    ];
+    let mut detected_lines = Vec::new();
+    for start_position in start_positions {
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let lines_clone = lines.clone();
+        log_watcher
+            .register_with_options(
+                log_path,
+                move |log_event: LogEvent| {
+                    let lines = lines_clone.clone();
+                    async move {
+                        if let Some(line) = log_event.get_line() {
+                            lines.lock().await.push(line[21..].to_owned());
+                        }
+                    }
+                },
+                RegisterOptions::new().with_start_position(start_position),
+            )
+            .await
+            .unwrap();
+        detected_lines.push(lines);
+    }
+
+    let monitor_handle = log_watcher.start(Duration::from_millis(100)).await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+
+    file.write_all(b"2023-05-01T13:00:00Z line 4\n")
+        .await
+        .unwrap();
+    file.flush().await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+
+    monitor_handle.shutdown().await.unwrap();
+
+    let expected_lines = vec![
+        vec!["line 1", "line 2", "line 3", "line 4"],
+        vec!["line 4"],
+        vec!["line 2", "line 3", "line 4"],
+        vec!["line 2", "line 3", "line 4"],
+        vec!["line 2", "line 3", "line 4"],
+    ];
+    for (lines, expected_lines) in detected_lines.iter().zip(expected_lines) {
+        assert_eq!(*lines.lock().await, expected_lines);
+    }
+
+    // remove test log file
+    remove_file(log_path).await.unwrap();
+}
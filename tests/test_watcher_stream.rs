@@ -0,0 +1,144 @@
+#![cfg(feature = "async-std")]
+
+use async_log_watch::{LogStream, LogWatcher, MemoryOffsetStore, RegisterOptions};
+
+use async_std::{
+    fs::{remove_file, rename, File, OpenOptions},
+    future::timeout,
+    io::prelude::*,
+    prelude::*,
+    task::{self, sleep},
+};
+
+use std::sync::Arc;
+use std::time::Duration;
+
+#[async_std::test]
+async fn log_watcher_stream_test() {
+    // ready for log file
+    let log_path = "test_log_stream.txt";
+    let _ = remove_file(log_path).await; // remove the file if it exists
+    let mut file = File::create(log_path).await.unwrap();
+
+    let mut log_watcher = LogWatcher::new();
+    let stream = log_watcher
+        .register_stream(log_path, Some(vec!["error"]))
+        .await
+        .unwrap();
+
+    // start monitoring
+    let _monitoring_handle = task::spawn(async move {
+        log_watcher
+            .monitoring(Duration::from_millis(100))
+            .await
+            .unwrap();
+    });
+    sleep(Duration::from_millis(500)).await;
+
+    for line in ["error 1\n", "info 2\n", "error 3\n", "error 4\n"] {
+        file.write_all(line.as_bytes()).await.unwrap();
+        file.flush().await.unwrap();
+        sleep(Duration::from_millis(100)).await;
+    }
+
+    let mut lines = stream
+        .filter_map(|log_event| log_event.get_line().cloned())
+        .take(3);
+    let lines = timeout(Duration::from_secs(5), async {
+        let mut received = Vec::new();
+        while let Some(line) = lines.next().await {
+            received.push(line);
+        }
+        received
+    })
+    .await
+    .unwrap();
+
+    // remove test log file
+    remove_file(log_path).await.unwrap();
+
+    assert_eq!(lines, vec!["error 1", "error 3", "error 4"]);
+}
+
+// the lines of the next `n` events of the stream.
+async fn next_lines(stream: &mut LogStream, n: usize) -> Vec<String> {
+    timeout(Duration::from_secs(5), async {
+        let mut received = Vec::new();
+        while received.len() < n {
+            let log_event = stream.next().await.unwrap();
+            if let Some(line) = log_event.get_line() {
+                received.push(line.clone());
+            }
+        }
+        received
+    })
+    .await
+    .unwrap()
+}
+
+#[async_std::test]
+async fn log_watcher_stream_catch_up_test() {
+    // ready for log file
+    let log_path = "test_log_stream_catch_up.txt";
+    let rotated_path = "test_log_stream_catch_up.txt.1";
+    let _ = remove_file(log_path).await; // remove the files if they exist
+    let _ = remove_file(rotated_path).await;
+    let mut file = File::create(log_path).await.unwrap();
+
+    let offset_store = Arc::new(MemoryOffsetStore::new());
+    let options = || {
+        RegisterOptions::new()
+            .with_offset_store(offset_store.clone(), "stream")
+            .with_stream_capacity(2)
+    };
+
+    let mut log_watcher = LogWatcher::new();
+    let mut stream = log_watcher
+        .register_stream_with_options(log_path, options())
+        .await
+        .unwrap();
+    let monitor_handle = log_watcher.start(Duration::from_millis(100)).await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+    file.write_all(b"line 1\n").await.unwrap();
+    file.flush().await.unwrap();
+    assert_eq!(next_lines(&mut stream, 1).await, vec!["line 1"]);
+    monitor_handle.shutdown().await.unwrap();
+    drop(stream);
+
+    // more lines than the capacity of the stream are written and rotated while stopped
+    let mut file = OpenOptions::new()
+        .append(true)
+        .open(log_path)
+        .await
+        .unwrap();
+    for i in 2..=10 {
+        file.write_all(format!("line {}\n", i).as_bytes())
+            .await
+            .unwrap();
+    }
+    file.flush().await.unwrap();
+    rename(log_path, rotated_path).await.unwrap();
+    let mut file = File::create(log_path).await.unwrap();
+    file.write_all(b"new 1\n").await.unwrap();
+    file.flush().await.unwrap();
+
+    // the registration returns before the lines of the rotated copy are read
+    let mut log_watcher = LogWatcher::new();
+    let mut stream = timeout(
+        Duration::from_secs(5),
+        log_watcher.register_stream_with_options(log_path, options()),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    let monitor_handle = log_watcher.start(Duration::from_millis(100)).await.unwrap();
+
+    let mut expected: Vec<String> = (2..=10).map(|i| format!("line {}", i)).collect();
+    expected.push("new 1".to_owned());
+    assert_eq!(next_lines(&mut stream, 10).await, expected);
+    monitor_handle.shutdown().await.unwrap();
+
+    // remove test log files
+    remove_file(log_path).await.unwrap();
+    remove_file(rotated_path).await.unwrap();
+}
@@ -0,0 +1,85 @@
+#![cfg(feature = "async-std")]
+
+use async_log_watch::{LogEvent, LogWatcher};
+
+use async_std::{
+    fs::{remove_file, File},
+    io::prelude::*,
+    sync::Mutex,
+    task::sleep,
+};
+
+use std::sync::Arc;
+use std::time::Duration;
+
+#[async_std::test]
+async fn log_watcher_subscribers_test() {
+    // ready for log file
+    let log_path = "test_log_subscribers.txt";
+    let _ = remove_file(log_path).await; // remove the file if it exists
+    let mut file = File::create(log_path).await.unwrap();
+
+    let mut log_watcher = LogWatcher::new();
+
+    // every line
+    let all_lines = Arc::new(Mutex::new(Vec::new()));
+    let all_lines_clone = all_lines.clone();
+    let all_id = log_watcher
+        .register(
+            log_path,
+            move |log_event: LogEvent| {
+                let all_lines = all_lines_clone.clone();
+                async move {
+                    if let Some(line) = log_event.get_line() {
+                        all_lines.lock().await.push(line.clone());
+                    }
+                }
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+    // the errors only
+    let error_lines = Arc::new(Mutex::new(Vec::new()));
+    let error_lines_clone = error_lines.clone();
+    let error_id = log_watcher
+        .register(
+            log_path,
+            move |log_event: LogEvent| {
+                let error_lines = error_lines_clone.clone();
+                async move {
+                    if let Some(line) = log_event.get_line() {
+                        error_lines.lock().await.push(line.clone());
+                    }
+                }
+            },
+            Some(vec!["ERROR"]),
+        )
+        .await
+        .unwrap();
+    assert_ne!(all_id, error_id);
+
+    let monitor_handle = log_watcher.start(Duration::from_millis(100)).await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+
+    file.write_all(b"INFO 1\nERROR 2\n").await.unwrap();
+    file.flush().await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+    assert_eq!(*all_lines.lock().await, vec!["INFO 1", "ERROR 2"]);
+    assert_eq!(*error_lines.lock().await, vec!["ERROR 2"]);
+
+    // the other registration keeps receiving the lines
+    log_watcher.unsubscribe(all_id).await.unwrap();
+
+    file.write_all(b"INFO 3\nERROR 4\n").await.unwrap();
+    file.flush().await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+    assert_eq!(*all_lines.lock().await, vec!["INFO 1", "ERROR 2"]);
+    assert_eq!(*error_lines.lock().await, vec!["ERROR 2", "ERROR 4"]);
+
+    monitor_handle.shutdown().await.unwrap();
+
+    // remove test log file
+    remove_file(log_path).await.unwrap();
+}
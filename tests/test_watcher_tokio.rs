@@ -0,0 +1,108 @@
+#![cfg(feature = "tokio")]
+
+use async_log_watch::{LogEvent, LogWatcher};
+
+use futures_core::Stream;
+use tokio::{
+    fs::{remove_file, File},
+    io::AsyncWriteExt,
+    sync::Mutex,
+    time::{sleep, timeout},
+};
+
+use std::future::poll_fn;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn log_watcher_tokio_test() {
+    // ready for log files
+    let log_path = "test_log_tokio.txt";
+    let stream_log_path = "test_log_tokio_stream.txt";
+    let _ = remove_file(log_path).await; // remove the files if they exist
+    let _ = remove_file(stream_log_path).await;
+    let mut file = File::create(log_path).await.unwrap();
+    let mut stream_file = File::create(stream_log_path).await.unwrap();
+
+    let mut log_watcher = LogWatcher::new();
+
+    let detected_lines = Arc::new(Mutex::new(Vec::new()));
+
+    let detected_lines_clone = detected_lines.clone();
+    log_watcher
+        .register(
+            log_path,
+            move |log_event: LogEvent| {
+                let detected_lines = detected_lines_clone.clone();
+                async move {
+                    if let Some(line) = log_event.get_line() {
+                        detected_lines.lock().await.push(line.clone());
+                    }
+                }
+            },
+            None,
+        )
+        .await
+        .unwrap();
+    let mut stream = log_watcher
+        .register_stream(stream_log_path, None)
+        .await
+        .unwrap();
+
+    // start monitoring
+    let _monitoring_handle = tokio::spawn(async move {
+        log_watcher
+            .monitoring(Duration::from_millis(100))
+            .await
+            .unwrap();
+    });
+    sleep(Duration::from_millis(500)).await;
+
+    file.write_all(b"line 1\n").await.unwrap();
+    file.flush().await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+    file.write_all(b"line 2\nline 3\n").await.unwrap();
+    file.flush().await.unwrap();
+    stream_file.write_all(b"line 4\n").await.unwrap();
+    stream_file.flush().await.unwrap();
+
+    let log_event = timeout(
+        Duration::from_secs(5),
+        poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    sleep(Duration::from_millis(500)).await;
+
+    // remove test log files
+    remove_file(log_path).await.unwrap();
+    remove_file(stream_log_path).await.unwrap();
+
+    assert_eq!(
+        *detected_lines.lock().await,
+        vec!["line 1", "line 2", "line 3"]
+    );
+    assert_eq!(log_event.get_line().unwrap(), "line 4");
+}
+
+#[test]
+fn log_watcher_tokio_stream_drop_test() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let handle = LogWatcher::new().handle();
+    let stream = runtime
+        .block_on(handle.register_stream("test_log_tokio_drop.txt", None))
+        .unwrap();
+    let other_stream = runtime
+        .block_on(handle.register_stream("test_log_tokio_drop.txt", None))
+        .unwrap();
+
+    // dropped outside of the runtime, the registration is unsubscribed by the runtime
+    drop(stream);
+    runtime.block_on(async { sleep(Duration::from_millis(100)).await });
+
+    // or stays once the runtime is shut down
+    drop(runtime);
+    drop(other_stream);
+}